@@ -0,0 +1,88 @@
+use clap::{Command, Arg};
+use std::{
+    env,
+    ffi::OsString,
+    io::{self, BufRead, Write},
+};
+
+type MyResult<T> = clr_io::Result<T>;
+
+#[derive(Debug)]
+pub struct Config {
+    text: Vec<String>,
+    omit_newline: bool,
+}
+
+// --------------------------------------------------
+pub fn get_args() -> MyResult<Config> {
+    get_args_from(env::args_os())
+}
+
+// --------------------------------------------------
+pub fn get_args_from<I>(args: I) -> MyResult<Config>
+where
+    I: IntoIterator<Item = OsString>,
+{
+    let matches = Command::new("echor")
+        .version("0.1.0")
+        .author("Ken Youens-Clark <kyclark@gmail.com>")
+        .about("Rust echo")
+        .arg(
+            Arg::new("text")
+                .allow_invalid_utf8(true)
+                .value_name("TEXT")
+                .help("Input text")
+                .required(true)
+                .min_values(1),
+        )
+        .arg(
+            Arg::new("omit_newline")
+                .short('n')
+                .help("Do not print newline")
+                .takes_value(false),
+        )
+        .try_get_matches_from(args)?;
+
+    Ok(Config {
+        text: matches.values_of_lossy("text").unwrap(),
+        omit_newline: matches.is_present("omit_newline"),
+    })
+}
+
+// --------------------------------------------------
+pub fn run(config: Config) -> MyResult<()> {
+    run_with(
+        config,
+        io::stdin().lock(),
+        io::stdout().lock(),
+        io::stderr(),
+    )
+}
+
+// --------------------------------------------------
+pub fn run_with(
+    config: Config,
+    _stdin: impl BufRead,
+    mut stdout: impl Write,
+    _stderr: impl Write,
+) -> MyResult<()> {
+    let ending = if config.omit_newline { "" } else { "\n" };
+    write!(stdout, "{}{}", config.text.join(" "), ending)?;
+    Ok(())
+}
+
+// --------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::{get_args_from, run_with};
+    use std::{ffi::OsString, io};
+
+    #[test]
+    fn test_run_with() {
+        let args = ["echor", "-n", "Hello", "there"];
+        let config = get_args_from(args.iter().map(OsString::from)).unwrap();
+        let mut stdout = vec![];
+        run_with(config, io::empty(), &mut stdout, vec![]).unwrap();
+        assert_eq!(stdout, b"Hello there");
+    }
+}
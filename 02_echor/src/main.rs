@@ -1,27 +1,5 @@
-use clap::{Command, Arg};
-
 fn main() {
-    let matches = Command::new("echor")
-        .version("0.1.0")
-        .author("Ken Youens-Clark <kyclark@gmail.com>")
-        .about("Rust echo")
-        .arg(
-            Arg::new("text")
-                .allow_invalid_utf8(true)
-                .value_name("TEXT")
-                .help("Input text")
-                .required(true)
-                .min_values(1),
-        )
-        .arg(
-            Arg::new("omit_newline")
-                .short('n')
-                .help("Do not print newline")
-                .takes_value(false),
-        )
-        .get_matches();
-
-    let text = matches.values_of_lossy("text").unwrap();
-    let omit_newline = matches.is_present("omit_newline");
-    print!("{}{}", text.join(" "), if omit_newline { "" } else { "\n" });
+    if let Err(e) = echor::get_args().and_then(echor::run) {
+        clr_io::exit("echor", e);
+    }
 }
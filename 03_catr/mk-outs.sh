@@ -11,6 +11,9 @@ EMPTY="$ROOT/empty.txt"
 FOX="$ROOT/fox.txt"
 SPIDERS="$ROOT/spiders.txt"
 BUSTLE="$ROOT/the-bustle.txt"
+CTRL="$ROOT/ctrl.txt"
+BLANK_END="$ROOT/blank-end.txt"
+BLANK_START="$ROOT/blank-start.txt"
 ALL="$EMPTY $FOX $SPIDERS $BUSTLE"
 
 for FILE in $ALL; do
@@ -20,11 +23,25 @@ for FILE in $ALL; do
     cat -b $FILE > ${OUT_DIR}/${BASENAME}.b.out
 done
 
+# GNU cat keeps numbering across files, as catr does by default
 cat    $ALL > $OUT_DIR/all.out
 cat -n $ALL > $OUT_DIR/all.n.out
 cat -b $ALL > $OUT_DIR/all.b.out
 
+# catr --per-file restarts the numbering with each file
+for FILE in $ALL; do cat -n $FILE; done > $OUT_DIR/all.n.per-file.out
+for FILE in $ALL; do cat -b $FILE; done > $OUT_DIR/all.b.per-file.out
+cat -sn $CTRL $CTRL > $OUT_DIR/$(basename $CTRL).sn.continuous.out
+cat -s $BLANK_END $BLANK_START > $OUT_DIR/blanks.s.out
+nl -ba -w3 -s': ' $BUSTLE > $OUT_DIR/$(basename $BUSTLE).w3.out
+
 cat    < $BUSTLE > $OUT_DIR/$(basename $BUSTLE).stdin.out
 cat -n < $BUSTLE > $OUT_DIR/$(basename $BUSTLE).n.stdin.out
 cat -b < $BUSTLE > $OUT_DIR/$(basename $BUSTLE).b.stdin.out
 
+
+BASENAME=$(basename "$CTRL")
+cat $CTRL > ${OUT_DIR}/${BASENAME}.out
+for FLAG in n b A e t E T v s sn sb An Ab; do
+    cat -$FLAG $CTRL > ${OUT_DIR}/${BASENAME}.${FLAG}.out
+done
@@ -1,19 +1,52 @@
+use bzip2::read::MultiBzDecoder;
 use clap::{Command, Arg};
-use std::error::Error;
-use std::fs::File;
-use std::io::{self, BufRead, BufReader};
+use clr_io::{Error, Input};
+use flate2::read::MultiGzDecoder;
+use std::{
+    env,
+    ffi::OsString,
+    io::{self, BufRead, BufReader, BufWriter, Cursor, Read, Write},
+};
+use xz2::read::XzDecoder;
 
-type MyResult<T> = Result<T, Box<dyn Error>>;
+type MyResult<T> = clr_io::Result<T>;
+
+const PRG: &str = "catr";
+
+const BUF_SIZE: usize = 128 * 1024;
 
 #[derive(Debug)]
 pub struct Config {
     files: Vec<String>,
     number_lines: bool,
     number_nonblank_lines: bool,
+    show_ends: bool,
+    show_tabs: bool,
+    show_nonprinting: bool,
+    squeeze_blank: bool,
+    continuous: bool,
+    number_width: usize,
+    number_separator: String,
+    decompress: bool,
+}
+
+#[derive(Debug, Default)]
+struct LineState {
+    last_num: usize,
+    prev_blank: bool,
+    mid_line: bool,
 }
 
 // --------------------------------------------------
 pub fn get_args() -> MyResult<Config> {
+    get_args_from(env::args_os())
+}
+
+// --------------------------------------------------
+pub fn get_args_from<I>(args: I) -> MyResult<Config>
+where
+    I: IntoIterator<Item = OsString>,
+{
     let matches = Command::new("catr")
         .version("0.1.0")
         .author("Ken Youens-Clark <kyclark@gmail.com>")
@@ -41,47 +74,413 @@ pub fn get_args() -> MyResult<Config> {
                 .help("Number non-blank lines")
                 .takes_value(false),
         )
-        .get_matches();
+        .arg(
+            Arg::new("show_all")
+                .short('A')
+                .long("show-all")
+                .help("Equivalent to -vET")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::new("e")
+                .short('e')
+                .help("Equivalent to -vE")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::new("show_ends")
+                .short('E')
+                .long("show-ends")
+                .help("Display $ at end of each line")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::new("squeeze_blank")
+                .short('s')
+                .long("squeeze-blank")
+                .help("Suppress repeated empty output lines")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::new("t")
+                .short('t')
+                .help("Equivalent to -vT")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::new("show_tabs")
+                .short('T')
+                .long("show-tabs")
+                .help("Display TAB characters as ^I")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::new("show_nonprinting")
+                .short('v')
+                .long("show-nonprinting")
+                .help("Use ^ and M- notation, except for LFD and TAB")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::new("continuous")
+                .long("continuous")
+                .help("Continue line numbers across files (default)")
+                .takes_value(false)
+                .overrides_with("per_file"),
+        )
+        .arg(
+            Arg::new("per_file")
+                .long("per-file")
+                .help("Restart line numbers with each file")
+                .takes_value(false)
+                .overrides_with("continuous"),
+        )
+        .arg(
+            Arg::new("number_width")
+                .long("number-width")
+                .value_name("WIDTH")
+                .help("Width of line numbers")
+                .default_value("6"),
+        )
+        .arg(
+            Arg::new("number_separator")
+                .long("number-separator")
+                .value_name("SEP")
+                .help("Text between line number and line")
+                .default_value("\t")
+                .hide_default_value(true),
+        )
+        .arg(
+            Arg::new("decompress")
+                .short('z')
+                .long("decompress")
+                .help("Decompress gzip, bzip2, xz and zstd input")
+                .takes_value(false),
+        )
+        .try_get_matches_from(args)?;
+
+    let number_width = matches
+        .value_of("number_width")
+        .map(parse_positive_int)
+        .transpose()
+        .map_err(|e| format!("illegal number width -- {}", e))?;
+
+    let show_all = matches.is_present("show_all");
+    let e = matches.is_present("e");
+    let t = matches.is_present("t");
 
     Ok(Config {
         files: matches.values_of_lossy("files").unwrap(),
         number_lines: matches.is_present("number"),
         number_nonblank_lines: matches.is_present("number_nonblank"),
+        show_ends: show_all || e || matches.is_present("show_ends"),
+        show_tabs: show_all || t || matches.is_present("show_tabs"),
+        show_nonprinting: show_all
+            || e
+            || t
+            || matches.is_present("show_nonprinting"),
+        squeeze_blank: matches.is_present("squeeze_blank"),
+        continuous: !matches.is_present("per_file"),
+        number_width: number_width.unwrap(),
+        number_separator: matches.value_of("number_separator").unwrap().into(),
+        decompress: matches.is_present("decompress"),
     })
 }
 
 // --------------------------------------------------
 pub fn run(config: Config) -> MyResult<()> {
-    for filename in config.files {
-        match open(&filename) {
-            Err(e) => eprintln!("{}: {}", filename, e),
-            Ok(file) => {
-                let mut last_num = 0;
-                for (line_num, line_result) in file.lines().enumerate() {
-                    let line = line_result?;
-                    if config.number_lines {
-                        println!("{:6}\t{}", line_num + 1, line);
-                    } else if config.number_nonblank_lines {
-                        if !line.is_empty() {
-                            last_num += 1;
-                            println!("{:6}\t{}", last_num, line);
-                        } else {
-                            println!();
-                        }
-                    } else {
-                        println!("{}", line);
-                    }
+    if !is_plain(&config) || config.decompress {
+        return run_with(
+            config,
+            io::stdin().lock(),
+            io::stdout().lock(),
+            io::stderr(),
+        );
+    }
+
+    // Copy each file straight to the real stdout so that io::copy can
+    // hand the work to the kernel
+    let mut stdout = io::stdout().lock();
+    let mut stderr = io::stderr();
+    let mut failed = false;
+    for filename in &config.files {
+        let res = clr_io::open_input_with(filename, io::stdin().lock())
+            .and_then(|input| {
+                clr_io::reading(filename, &mut stdout, |out| {
+                    match input {
+                        Input::Stdin(mut stdin) => out.copy_from(&mut stdin),
+                        Input::File(mut file) => out.copy_from(&mut file),
+                    }?;
+                    Ok(())
+                })
+            });
+        failed |= skip_bad_file(res, &mut stderr)?;
+    }
+
+    if failed {
+        return Err(Error::Exit(1));
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+pub fn run_with(
+    config: Config,
+    mut stdin: impl BufRead,
+    stdout: impl Write,
+    mut stderr: impl Write,
+) -> MyResult<()> {
+    let mut out = BufWriter::with_capacity(BUF_SIZE, stdout);
+    let plain = is_plain(&config);
+
+    let mut state = LineState::default();
+    let mut failed = false;
+    for filename in &config.files {
+        let res = if plain && !config.decompress {
+            clr_io::open_input_with(filename, &mut stdin)
+                .and_then(|input| copy_raw(filename, input, out.get_mut()))
+        } else {
+            open(filename, &mut stdin, config.decompress).and_then(|file| {
+                // Blank squeezing and unterminated lines always carry on
+                // into the next file, as in GNU cat
+                if !config.continuous {
+                    state.last_num = 0;
                 }
+                let res = cat(filename, file, &config, &mut state, &mut out);
+                out.flush()?;
+                res
+            })
+        };
+        failed |= skip_bad_file(res, &mut stderr)?;
+    }
+
+    if failed {
+        return Err(Error::Exit(1));
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+// Nothing to format, so the bytes can be passed through untouched
+fn is_plain(config: &Config) -> bool {
+    !(config.number_lines
+        || config.number_nonblank_lines
+        || config.show_ends
+        || config.show_tabs
+        || config.show_nonprinting
+        || config.squeeze_blank)
+}
+
+// --------------------------------------------------
+// A file that can't be opened or read is reported and skipped, but
+// failing to write the output ends the run. Returns whether it failed.
+fn skip_bad_file(res: MyResult<()>, stderr: &mut impl Write) -> MyResult<bool> {
+    match res {
+        Err(e @ (Error::File { .. } | Error::IsDir(_))) => {
+            clr_io::report_to(stderr, PRG, &e);
+            Ok(true)
+        }
+        res => res.map(|()| false),
+    }
+}
+
+// --------------------------------------------------
+// The buffered copy for run_with, whose reader and writer can be
+// anything, in whatever chunks the reader hands back. A read error is
+// tied to the file rather than to the output.
+fn copy_raw(
+    filename: &str,
+    input: impl Read,
+    out: &mut impl Write,
+) -> MyResult<()> {
+    let mut input = BufReader::with_capacity(BUF_SIZE, input);
+    loop {
+        let buf = match input.fill_buf() {
+            Ok([]) => break,
+            Ok(buf) => buf,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(Error::file(filename, e)),
+        };
+        out.write_all(buf)?;
+        let len = buf.len();
+        input.consume(len);
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+fn cat(
+    filename: &str,
+    mut file: impl BufRead,
+    config: &Config,
+    state: &mut LineState,
+    out: &mut impl Write,
+) -> MyResult<()> {
+    let mut line = Vec::new();
+    let mut display = Vec::new();
+
+    loop {
+        line.clear();
+        let read = file
+            .read_until(b'\n', &mut line)
+            .map_err(|e| Error::file(filename, e))?;
+        if read == 0 {
+            break;
+        }
+
+        let (body, has_newline) = match line.strip_suffix(b"\n") {
+            Some(body) => (body, true),
+            None => (&line[..], false),
+        };
+
+        // A line left unterminated by the previous file carries on here
+        let continued = state.mid_line;
+        state.mid_line = !has_newline;
+
+        let blank = body.is_empty() && !continued;
+        if config.squeeze_blank && blank && state.prev_blank {
+            continue;
+        }
+        state.prev_blank = blank;
+
+        let numbered =
+            config.number_lines || (config.number_nonblank_lines && !blank);
+        if numbered && !continued {
+            state.last_num += 1;
+            write!(
+                out,
+                "{:width$}{}",
+                state.last_num,
+                config.number_separator,
+                width = config.number_width
+            )?;
+        }
+
+        // GNU cat shows a CR that ends a line as "^M" under -E
+        let (body, cr) = match body.strip_suffix(b"\r") {
+            Some(rest) if config.show_ends && has_newline => (rest, true),
+            _ => (body, false),
+        };
+
+        if config.show_tabs || config.show_nonprinting {
+            display.clear();
+            for &byte in body {
+                push_visible(byte, config, &mut display);
             }
+            out.write_all(&display)?;
+        } else {
+            out.write_all(body)?;
+        }
+
+        if cr {
+            out.write_all(b"^M")?;
+        }
+        if has_newline {
+            if config.show_ends {
+                out.write_all(b"$")?;
+            }
+            out.write_all(b"\n")?;
         }
     }
     Ok(())
 }
 
 // --------------------------------------------------
-fn open(filename: &str) -> MyResult<Box<dyn BufRead>> {
-    match filename {
-        "-" => Ok(Box::new(BufReader::new(io::stdin()))),
-        _ => Ok(Box::new(BufReader::new(File::open(filename)?))),
+fn push_visible(byte: u8, config: &Config, display: &mut Vec<u8>) {
+    match byte {
+        b'\t' if config.show_tabs => display.extend_from_slice(b"^I"),
+        b'\t' => display.push(byte),
+        _ if !config.show_nonprinting => display.push(byte),
+        0..=31 => display.extend_from_slice(&[b'^', byte + 64]),
+        32..=126 => display.push(byte),
+        127 => display.extend_from_slice(b"^?"),
+        128..=159 => display.extend_from_slice(&[b'M', b'-', b'^', byte - 64]),
+        160..=254 => display.extend_from_slice(&[b'M', b'-', byte - 128]),
+        255 => display.extend_from_slice(b"M-^?"),
+    }
+}
+
+// --------------------------------------------------
+fn open<'a>(
+    filename: &str,
+    stdin: impl Read + 'a,
+    decompress: bool,
+) -> MyResult<Box<dyn BufRead + 'a>> {
+    let file: Box<dyn Read + 'a> =
+        Box::new(clr_io::open_input_with(filename, stdin)?);
+    let file = if decompress {
+        decoder(file).map_err(|e| Error::file(filename, e))?
+    } else {
+        file
+    };
+    Ok(Box::new(BufReader::with_capacity(BUF_SIZE, file)))
+}
+
+// --------------------------------------------------
+// Pick a decoder from the magic bytes at the start of the stream.
+// Anything unrecognized is passed through as is, like `zcat -f`.
+fn decoder<'a>(mut file: Box<dyn Read + 'a>) -> io::Result<Box<dyn Read + 'a>> {
+    let mut magic = Vec::with_capacity(6);
+    file.by_ref().take(6).read_to_end(&mut magic)?;
+    let file = Cursor::new(magic.clone()).chain(file);
+
+    Ok(match magic.as_slice() {
+        [0x1f, 0x8b, ..] => Box::new(MultiGzDecoder::new(file)),
+        [b'B', b'Z', b'h', ..] => Box::new(MultiBzDecoder::new(file)),
+        [0xfd, b'7', b'z', b'X', b'Z', 0x00] => {
+            Box::new(XzDecoder::new_multi_decoder(file))
+        }
+        [0x28, 0xb5, 0x2f, 0xfd, ..] => {
+            Box::new(zstd::stream::read::Decoder::new(file)?)
+        }
+        _ => Box::new(file),
+    })
+}
+
+// --------------------------------------------------
+fn parse_positive_int(val: &str) -> MyResult<usize> {
+    match val.parse() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(From::from(val)),
+    }
+}
+
+// --------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::{get_args_from, run_with};
+    use std::{ffi::OsString, io::Cursor};
+
+    fn args(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn test_get_args_from() {
+        let config = get_args_from(args(&["catr", "-A", "foo"])).unwrap();
+        assert_eq!(config.files, ["foo"]);
+        assert!(config.show_ends && config.show_tabs);
+
+        let err = get_args_from(args(&["catr", "--help"])).unwrap_err();
+        assert_eq!(err.exit_code(), 0);
+        let err = get_args_from(args(&["catr", "--bad"])).unwrap_err();
+        assert_eq!(err.exit_code(), 2);
+    }
+
+    #[test]
+    fn test_run_with() {
+        let config =
+            get_args_from(args(&["catr", "-nE", "-", "no-such-file"])).unwrap();
+        let stdin = Cursor::new("a\n\nb\n");
+        let (mut stdout, mut stderr) = (vec![], vec![]);
+        let res = run_with(config, stdin, &mut stdout, &mut stderr);
+        assert_eq!(res.unwrap_err().exit_code(), 1);
+        assert_eq!(
+            String::from_utf8(stdout).unwrap(),
+            "     1\ta$\n     2\t$\n     3\tb$\n"
+        );
+        assert!(String::from_utf8(stderr)
+            .unwrap()
+            .starts_with("catr: no-such-file: "));
     }
 }
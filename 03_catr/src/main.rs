@@ -1,6 +1,5 @@
 fn main() {
     if let Err(e) = catr::get_args().and_then(catr::run) {
-        eprintln!("{}", e);
-        std::process::exit(1);
+        clr_io::exit("catr", e);
     }
 }
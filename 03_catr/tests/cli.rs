@@ -1,8 +1,12 @@
 use assert_cmd::Command;
+#[cfg(target_os = "linux")]
+use clr_io::test_support::{unreadable_error, UNREADABLE};
 use predicates::prelude::*;
 use rand::{distributions::Alphanumeric, Rng};
 use std::error::Error;
 use std::fs;
+use std::io::Write;
+use tempfile::NamedTempFile;
 
 type TestResult = Result<(), Box<dyn Error>>;
 
@@ -11,6 +15,9 @@ const EMPTY: &str = "tests/inputs/empty.txt";
 const FOX: &str = "tests/inputs/fox.txt";
 const SPIDERS: &str = "tests/inputs/spiders.txt";
 const BUSTLE: &str = "tests/inputs/the-bustle.txt";
+const CTRL: &str = "tests/inputs/ctrl.txt";
+const BLANK_END: &str = "tests/inputs/blank-end.txt";
+const BLANK_START: &str = "tests/inputs/blank-start.txt";
 
 // --------------------------------------------------
 #[test]
@@ -43,18 +50,71 @@ fn gen_bad_file() -> String {
 #[test]
 fn skips_bad_file() -> TestResult {
     let bad = gen_bad_file();
-    let expected = format!("{}: .* [(]os error 2[)]", bad);
+    let expected = format!("^catr: {}: .* [(]os error 2[)]\n$", bad);
     Command::cargo_bin(PRG)?
-        .arg(&bad)
+        .args([&bad, FOX])
         .assert()
-        .success()
-        .stderr(predicate::str::is_match(expected)?);
+        .failure()
+        .code(1)
+        .stderr(predicate::str::is_match(expected)?)
+        .stdout(fs::read_to_string("tests/expected/fox.txt.out")?);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn skips_dir() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-n", "tests", FOX])
+        .assert()
+        .failure()
+        .code(1)
+        .stderr("catr: tests: Is a directory\n")
+        .stdout(fs::read_to_string("tests/expected/fox.txt.n.out")?);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(target_os = "linux")]
+fn skips_unreadable_file() -> TestResult {
+    for (args, expected_file) in [
+        (&[UNREADABLE, FOX][..], "tests/expected/fox.txt.out"),
+        (&["-n", UNREADABLE, FOX], "tests/expected/fox.txt.n.out"),
+    ] {
+        Command::cargo_bin(PRG)?
+            .args(args)
+            .assert()
+            .failure()
+            .code(1)
+            .stderr(predicate::str::starts_with(unreadable_error(PRG)))
+            .stdout(fs::read_to_string(expected_file)?);
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+// Failing to write stdout ends the run without blaming the input, on
+// both the kernel copy and the formatting paths
+#[test]
+#[cfg(target_os = "linux")]
+fn dies_write_error() -> TestResult {
+    for args in [&[FOX, FOX][..], &["-n", FOX, FOX]] {
+        let bin = assert_cmd::cargo::cargo_bin(PRG);
+        let output = std::process::Command::new(bin)
+            .args(args)
+            .stdout(fs::File::create("/dev/full")?)
+            .output()?;
+        assert_eq!(output.status.code(), Some(1));
+        let stderr = String::from_utf8(output.stderr)?;
+        assert!(stderr.starts_with("catr: ") && !stderr.contains(FOX));
+    }
     Ok(())
 }
 
 // --------------------------------------------------
 fn run(args: &[&str], expected_file: &str) -> TestResult {
-    let expected = fs::read_to_string(expected_file)?;
+    let expected = fs::read(expected_file)?;
     Command::cargo_bin(PRG)?
         .args(args)
         .assert()
@@ -198,3 +258,306 @@ fn all_n() -> TestResult {
 fn all_b() -> TestResult {
     run(&[FOX, SPIDERS, BUSTLE, "-b"], "tests/expected/all.b.out")
 }
+
+// --------------------------------------------------
+#[test]
+fn ctrl() -> TestResult {
+    run(&[CTRL], "tests/expected/ctrl.txt.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn ctrl_n() -> TestResult {
+    run(&["-n", CTRL], "tests/expected/ctrl.txt.n.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn ctrl_b() -> TestResult {
+    run(&["-b", CTRL], "tests/expected/ctrl.txt.b.out")
+}
+
+// --------------------------------------------------
+fn random_bytes(len: usize) -> Vec<u8> {
+    let mut rng = rand::thread_rng();
+    (0..len).map(|_| rng.gen()).collect()
+}
+
+// --------------------------------------------------
+#[test]
+fn binary_file() -> TestResult {
+    let bytes = random_bytes(300_000);
+    let mut file = NamedTempFile::new()?;
+    file.write_all(&bytes)?;
+    let path = file.path().to_str().unwrap();
+
+    let mut expected = bytes.clone();
+    expected.extend_from_slice(&bytes);
+    Command::cargo_bin(PRG)?
+        .args([path, path])
+        .assert()
+        .success()
+        .stdout(expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn binary_stdin() -> TestResult {
+    let bytes = random_bytes(300_000);
+    Command::cargo_bin(PRG)?
+        .write_stdin(bytes.clone())
+        .assert()
+        .success()
+        .stdout(bytes);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn crlf_no_final_newline_n() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .arg("-n")
+        .write_stdin(&b"a\r\n\xffb\r\nc"[..])
+        .assert()
+        .success()
+        .stdout(&b"     1\ta\r\n     2\t\xffb\r\n     3\tc"[..]);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn ctrl_show_all() -> TestResult {
+    run(&["-A", CTRL], "tests/expected/ctrl.txt.A.out")?;
+    run(&["--show-all", CTRL], "tests/expected/ctrl.txt.A.out")?;
+    run(&["-vET", CTRL], "tests/expected/ctrl.txt.A.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn ctrl_e() -> TestResult {
+    run(&["-e", CTRL], "tests/expected/ctrl.txt.e.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn ctrl_t() -> TestResult {
+    run(&["-t", CTRL], "tests/expected/ctrl.txt.t.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn ctrl_show_ends() -> TestResult {
+    run(&["--show-ends", CTRL], "tests/expected/ctrl.txt.E.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn ctrl_show_tabs() -> TestResult {
+    run(&["--show-tabs", CTRL], "tests/expected/ctrl.txt.T.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn ctrl_show_nonprinting() -> TestResult {
+    run(&["-v", CTRL], "tests/expected/ctrl.txt.v.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn ctrl_squeeze_blank() -> TestResult {
+    run(&["--squeeze-blank", CTRL], "tests/expected/ctrl.txt.s.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn ctrl_squeeze_blank_n() -> TestResult {
+    run(&["-s", "-n", CTRL], "tests/expected/ctrl.txt.sn.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn ctrl_squeeze_blank_b() -> TestResult {
+    run(&["-sb", CTRL], "tests/expected/ctrl.txt.sb.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn ctrl_show_all_n() -> TestResult {
+    run(&["-A", "-n", CTRL], "tests/expected/ctrl.txt.An.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn ctrl_show_all_b() -> TestResult {
+    run(&["-Ab", CTRL], "tests/expected/ctrl.txt.Ab.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn all_n_continuous() -> TestResult {
+    // The last of --per-file and --continuous wins
+    run(
+        &["-n", "--per-file", "--continuous", EMPTY, FOX, SPIDERS, BUSTLE],
+        "tests/expected/all.n.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn all_b_continuous() -> TestResult {
+    run(
+        &["-b", "--continuous", EMPTY, FOX, SPIDERS, BUSTLE],
+        "tests/expected/all.b.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn all_n_per_file() -> TestResult {
+    run(
+        &["-n", "--continuous", "--per-file", EMPTY, FOX, SPIDERS, BUSTLE],
+        "tests/expected/all.n.per-file.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn all_b_per_file() -> TestResult {
+    run(
+        &["-b", "--per-file", EMPTY, FOX, SPIDERS, BUSTLE],
+        "tests/expected/all.b.per-file.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn ctrl_squeeze_blank_n_continuous() -> TestResult {
+    run(
+        &["-sn", "--continuous", CTRL, CTRL],
+        "tests/expected/ctrl.txt.sn.continuous.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn squeeze_blank_across_files() -> TestResult {
+    run(&["-s", BLANK_END, BLANK_START], "tests/expected/blanks.s.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_number_width_separator() -> TestResult {
+    run(
+        &[
+            "-n",
+            "--number-width",
+            "3",
+            "--number-separator",
+            ": ",
+            BUSTLE,
+        ],
+        "tests/expected/the-bustle.txt.w3.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_number_width() -> TestResult {
+    for bad in &["0", "foo"] {
+        Command::cargo_bin(PRG)?
+            .args(["-n", "--number-width", bad, FOX])
+            .assert()
+            .failure()
+            .stderr(format!("catr: illegal number width -- {}\n", bad));
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn decompress() -> TestResult {
+    for ext in &["gz", "bz2", "xz", "zst"] {
+        let file = format!("{}.{}", BUSTLE, ext);
+        run(&["-z", &file], "tests/expected/the-bustle.txt.out")?;
+        run(&["-z", "-n", &file], "tests/expected/the-bustle.txt.n.out")?;
+        run(
+            &["--decompress", "-b", &file],
+            "tests/expected/the-bustle.txt.b.out",
+        )?;
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn decompress_stdin() -> TestResult {
+    let expected = fs::read("tests/expected/the-bustle.txt.n.stdin.out")?;
+    for ext in &["gz", "bz2", "xz", "zst"] {
+        let input = fs::read(format!("{}.{}", BUSTLE, ext))?;
+        Command::cargo_bin(PRG)?
+            .args(["-z", "-n"])
+            .write_stdin(input)
+            .assert()
+            .success()
+            .stdout(expected.clone());
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn decompress_concatenated_members() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-z", "tests/inputs/multi.gz"])
+        .assert()
+        .success()
+        .stdout(format!(
+            "{}{}",
+            fs::read_to_string(FOX)?,
+            fs::read_to_string(SPIDERS)?
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn decompress_skips_corrupt_input() -> TestResult {
+    let fox = fs::read_to_string(FOX)?;
+    for ext in &["gz", "bz2", "xz", "zst"] {
+        // Cut each archive off partway through its compressed data
+        let input = fs::read(format!("{}.{}", BUSTLE, ext))?;
+        let mut file = NamedTempFile::new()?;
+        file.write_all(&input[..40])?;
+        let path = file.path().to_str().unwrap();
+        Command::cargo_bin(PRG)?
+            .args(["-z", path, FOX])
+            .assert()
+            .failure()
+            .code(1)
+            .stderr(predicate::str::starts_with(format!("catr: {}: ", path)))
+            .stdout(predicate::str::ends_with(fox.clone()));
+    }
+
+    // Nothing but the gzip magic number
+    let mut file = NamedTempFile::new()?;
+    file.write_all(&[0x1f, 0x8b])?;
+    let path = file.path().to_str().unwrap();
+    Command::cargo_bin(PRG)?
+        .args(["-z", path, FOX])
+        .assert()
+        .failure()
+        .stderr(format!("catr: {}: unexpected end of file\n", path))
+        .stdout(fox);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn decompress_passes_plain_input() -> TestResult {
+    run(
+        &["-z", EMPTY, FOX, SPIDERS, BUSTLE],
+        "tests/expected/all.out",
+    )?;
+    run(&["-z", CTRL], "tests/expected/ctrl.txt.out")
+}
@@ -13,6 +13,8 @@ for FILE in $INPUTS/*.txt; do
     head -c 1 $FILE > ${OUT_DIR}/${BASENAME}.c1.out
     head -c 2 $FILE > ${OUT_DIR}/${BASENAME}.c2.out
     head -c 4 $FILE > ${OUT_DIR}/${BASENAME}.c4.out
+    head -n -2 $FILE > ${OUT_DIR}/${BASENAME}.n-2.out
+    head -c -4 $FILE > ${OUT_DIR}/${BASENAME}.c-4.out
 done
 
 ALL="$INPUTS/empty.txt $INPUTS/one.txt $INPUTS/two.txt $INPUTS/three.txt \
@@ -23,3 +25,10 @@ head -n 4 $ALL > $OUT_DIR/all.n4.out
 head -c 1 $ALL > $OUT_DIR/all.c1.out
 head -c 2 $ALL > $OUT_DIR/all.c2.out
 head -c 4 $ALL > $OUT_DIR/all.c4.out
+head -n -2 $ALL > $OUT_DIR/all.n-2.out
+head -c -4 $ALL > $OUT_DIR/all.c-4.out
+head -c 1K $ALL > $OUT_DIR/all.c1K.out
+head -n 1KiB $ALL > $OUT_DIR/all.n1KiB.out
+head -c -1b $ALL > $OUT_DIR/all.c-1b.out
+head -v $INPUTS/one.txt > $OUT_DIR/one.txt.v.out
+head -q -n 2 $ALL > $OUT_DIR/all.q.n2.out
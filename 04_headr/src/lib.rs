@@ -1,19 +1,55 @@
+use crate::Count::*;
 use clap::{Command, Arg};
-use std::error::Error;
-use std::fs::File;
-use std::io::{self, BufRead, BufReader, Read};
+use clr_io::{
+    size::{parse_size, SizeError},
+    Error,
+};
+use serde_json::json;
+use std::{
+    collections::VecDeque,
+    env,
+    ffi::OsString,
+    io::{self, BufRead, Write},
+};
 
-type MyResult<T> = Result<T, Box<dyn Error>>;
+type MyResult<T> = clr_io::Result<T>;
+
+const PRG: &str = "headr";
+
+#[derive(Debug, PartialEq)]
+enum Count {
+    First(usize),
+    AllBut(usize),
+}
+
+#[derive(Debug, PartialEq)]
+enum Headers {
+    Auto,
+    Always,
+    Never,
+}
 
 #[derive(Debug)]
 pub struct Config {
     files: Vec<String>,
-    lines: usize,
-    bytes: Option<usize>,
+    lines: Count,
+    bytes: Option<Count>,
+    chars: Option<Count>,
+    separator: u8,
+    headers: Headers,
+    json: bool,
 }
 
 // --------------------------------------------------
 pub fn get_args() -> MyResult<Config> {
+    get_args_from(env::args_os())
+}
+
+// --------------------------------------------------
+pub fn get_args_from<I>(args: I) -> MyResult<Config>
+where
+    I: IntoIterator<Item = OsString>,
+{
     let matches = Command::new("headr")
         .version("0.1.0")
         .author("Ken Youens-Clark <kyclark@gmail.com>")
@@ -23,7 +59,11 @@ pub fn get_args() -> MyResult<Config> {
                 .short('n')
                 .long("lines")
                 .value_name("LINES")
-                .help("Number of lines")
+                .help(
+                    "Number of lines (suffixes: b, K, M, G, KiB, KB, ...); \
+                     with a leading '-', all but the last",
+                )
+                .allow_hyphen_values(true)
                 .default_value("10"),
         )
         .arg(
@@ -33,7 +73,57 @@ pub fn get_args() -> MyResult<Config> {
                 .value_name("BYTES")
                 .takes_value(true)
                 .conflicts_with("lines")
-                .help("Number of bytes"),
+                .allow_hyphen_values(true)
+                .help(
+                    "Number of bytes (suffixes: b, K, M, G, KiB, KB, ...); \
+                     with a leading '-', all but the last",
+                ),
+        )
+        .arg(
+            Arg::new("chars")
+                .long("chars")
+                .value_name("CHARS")
+                .takes_value(true)
+                .conflicts_with_all(&["lines", "bytes"])
+                .allow_hyphen_values(true)
+                .help(
+                    "Number of UTF-8 characters; with a leading '-', \
+                     all but the last",
+                ),
+        )
+        .arg(
+            Arg::new("zero")
+                .short('z')
+                .long("zero-terminated")
+                .help("Line delimiter is NUL, not newline"),
+        )
+        .arg(
+            Arg::new("separator")
+                .long("separator")
+                .value_name("SEP")
+                .takes_value(true)
+                .conflicts_with("zero")
+                .help("Line delimiter: one byte, or \\0, \\t, \\n, \\xHH"),
+        )
+        .arg(
+            Arg::new("verbose")
+                .short('v')
+                .long("verbose")
+                .overrides_with("quiet")
+                .help("Always print headers giving file names"),
+        )
+        .arg(
+            Arg::new("quiet")
+                .short('q')
+                .long("quiet")
+                .visible_alias("silent")
+                .overrides_with("verbose")
+                .help("Never print headers giving file names"),
+        )
+        .arg(
+            Arg::new("json")
+                .long("json")
+                .help("Print a JSON object with the lines of each file"),
         )
         .arg(
             Arg::new("files")
@@ -43,80 +133,351 @@ pub fn get_args() -> MyResult<Config> {
                 .multiple_occurrences(true)
                 .default_value("-"),
         )
-        .get_matches();
+        .try_get_matches_from(args)?;
 
     let lines = matches
         .value_of("lines")
-        .map(parse_positive_int)
+        .map(parse_count)
         .transpose()
         .map_err(|e| format!("illegal line count -- {}", e))?;
 
     let bytes = matches
         .value_of("bytes")
-        .map(parse_positive_int)
+        .map(parse_count)
         .transpose()
         .map_err(|e| format!("illegal byte count -- {}", e))?;
 
+    let chars = matches
+        .value_of("chars")
+        .map(parse_count)
+        .transpose()
+        .map_err(|e| format!("illegal character count -- {}", e))?;
+
+    let separator = match matches.value_of("separator") {
+        Some(sep) => parse_separator(sep)
+            .ok_or_else(|| format!("illegal separator -- {}", sep))?,
+        None if matches.is_present("zero") => b'\0',
+        None => b'\n',
+    };
+
     Ok(Config {
         files: matches.values_of_lossy("files").unwrap(),
         lines: lines.unwrap(),
         bytes,
+        chars,
+        separator,
+        headers: if matches.is_present("verbose") {
+            Headers::Always
+        } else if matches.is_present("quiet") {
+            Headers::Never
+        } else {
+            Headers::Auto
+        },
+        json: matches.is_present("json"),
     })
 }
 
 // --------------------------------------------------
 pub fn run(config: Config) -> MyResult<()> {
-    let num_files = config.files.len();
+    run_with(
+        config,
+        io::stdin().lock(),
+        io::stdout().lock(),
+        io::stderr(),
+    )
+}
+
+// --------------------------------------------------
+pub fn run_with(
+    config: Config,
+    mut stdin: impl BufRead,
+    mut stdout: impl Write,
+    mut stderr: impl Write,
+) -> MyResult<()> {
+    let print_headers = match config.headers {
+        Headers::Always => true,
+        Headers::Never => false,
+        Headers::Auto => config.files.len() > 1,
+    };
+    let mut failed = false;
 
     for (file_num, filename) in config.files.iter().enumerate() {
-        match open(filename) {
-            Err(err) => eprintln!("{}: {}", filename, err),
-            Ok(mut file) => {
-                if num_files > 1 {
-                    println!(
+        match clr_io::open_with(filename, &mut stdin) {
+            Err(err) => {
+                clr_io::report_to(&mut stderr, PRG, &err);
+                failed = true;
+            }
+            Ok(file) => {
+                if print_headers && !config.json {
+                    writeln!(
+                        stdout,
                         "{}==> {} <==",
                         if file_num > 0 { "\n" } else { "" },
                         &filename
-                    );
+                    )?;
                 }
 
-                if let Some(num_bytes) = config.bytes {
-                    let mut handle = file.take(num_bytes as u64);
-                    let mut buffer = vec![0; num_bytes];
-                    let bytes_read = handle.read(&mut buffer)?;
-                    print!(
-                        "{}",
-                        String::from_utf8_lossy(&buffer[..bytes_read])
-                    );
+                let mut buf = Vec::new();
+                let res = if config.json {
+                    clr_io::reading(filename, &mut buf, |out| {
+                        print_head(file, &config, out)
+                    })
                 } else {
-                    let mut line = String::new();
-                    for _ in 0..config.lines {
-                        let bytes = file.read_line(&mut line)?;
-                        if bytes == 0 {
-                            break;
-                        }
-                        print!("{}", line);
-                        line.clear();
+                    clr_io::reading(filename, &mut stdout, |out| {
+                        print_head(file, &config, out)
+                    })
+                };
+
+                // A read error skips to the next file, a write error ends
+                // the run
+                match res {
+                    Err(err @ Error::File { .. }) => {
+                        clr_io::report_to(&mut stderr, PRG, &err);
+                        failed = true;
+                    }
+                    Err(err) => return Err(err),
+                    Ok(()) if config.json => {
+                        let lines = split_records(&buf, config.separator);
+                        let record =
+                            json!({ "file": filename, "lines": lines });
+                        writeln!(stdout, "{}", record)?;
                     }
+                    Ok(()) => {}
                 }
             }
         }
     }
+
+    if failed {
+        return Err(Error::Exit(1));
+    }
     Ok(())
 }
 
 // --------------------------------------------------
-fn open(filename: &str) -> MyResult<Box<dyn BufRead>> {
-    match filename {
-        "-" => Ok(Box::new(BufReader::new(io::stdin()))),
-        _ => Ok(Box::new(BufReader::new(File::open(filename)?))),
+fn print_head(
+    mut file: impl BufRead,
+    config: &Config,
+    out: &mut impl Write,
+) -> MyResult<()> {
+    // Output is the raw input bytes, whatever the encoding
+    match (&config.bytes, &config.chars, &config.lines) {
+        (Some(First(num_bytes)), _, _) => {
+            let mut handle = file.take(*num_bytes as u64);
+            io::copy(&mut handle, out)?;
+        }
+        (Some(AllBut(num_bytes)), _, _) => {
+            print_all_but_bytes(file, *num_bytes, out)?
+        }
+        (None, Some(First(num_chars)), _) => {
+            print_first_chars(file, *num_chars, out)?
+        }
+        (None, Some(AllBut(num_chars)), _) => {
+            print_all_but_chars(file, *num_chars, out)?
+        }
+        (None, None, First(num_lines)) => {
+            let mut line = Vec::new();
+            for _ in 0..*num_lines {
+                let bytes = file.read_until(config.separator, &mut line)?;
+                if bytes == 0 {
+                    break;
+                }
+                out.write_all(&line)?;
+                line.clear();
+            }
+        }
+        (None, None, AllBut(num_lines)) => {
+            print_all_but_lines(file, *num_lines, config.separator, out)?
+        }
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+// The records in `buf`, without their terminators, for the JSON output.
+fn split_records(buf: &[u8], separator: u8) -> Vec<String> {
+    if buf.is_empty() {
+        return vec![];
+    }
+    let buf = buf.strip_suffix(&[separator]).unwrap_or(buf);
+    buf.split(|&b| b == separator)
+        .map(|record| String::from_utf8_lossy(record).into_owned())
+        .collect()
+}
+
+// --------------------------------------------------
+// Hold back the last `num` lines in a ring buffer so that only they are
+// ever kept in memory, however long the input.
+fn print_all_but_lines(
+    mut file: impl BufRead,
+    num: usize,
+    separator: u8,
+    out: &mut impl Write,
+) -> MyResult<()> {
+    let mut ring = VecDeque::new();
+    loop {
+        let mut line = Vec::new();
+        if file.read_until(separator, &mut line)? == 0 {
+            break;
+        }
+        ring.push_back(line);
+        if ring.len() > num {
+            if let Some(line) = ring.pop_front() {
+                out.write_all(&line)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+// Likewise for bytes: at most `num` bytes plus one read buffer are held.
+fn print_all_but_bytes(
+    mut file: impl BufRead,
+    num: usize,
+    out: &mut impl Write,
+) -> MyResult<()> {
+    let mut ring: VecDeque<u8> = VecDeque::new();
+    loop {
+        let buf = file.fill_buf()?;
+        if buf.is_empty() {
+            break;
+        }
+        let len = buf.len();
+        ring.extend(buf);
+        file.consume(len);
+
+        if ring.len() > num {
+            let excess = ring.len() - num;
+            write_front(&mut ring, excess, out)?;
+        }
     }
+    Ok(())
+}
+
+// --------------------------------------------------
+// Characters are counted by their first byte, so invalid UTF-8 passes
+// through untouched rather than being replaced.
+fn is_char_start(byte: u8) -> bool {
+    byte & 0xC0 != 0x80
 }
 
 // --------------------------------------------------
+fn print_first_chars(
+    mut file: impl BufRead,
+    num: usize,
+    out: &mut impl Write,
+) -> MyResult<()> {
+    let mut seen = 0;
+    loop {
+        let buf = file.fill_buf()?;
+        if buf.is_empty() {
+            break;
+        }
+        // Stop at the byte that starts character number num + 1
+        let end = buf.iter().position(|&b| {
+            seen += usize::from(is_char_start(b));
+            seen > num
+        });
+        match end {
+            Some(end) => {
+                out.write_all(&buf[..end])?;
+                break;
+            }
+            None => {
+                out.write_all(buf)?;
+                let len = buf.len();
+                file.consume(len);
+            }
+        }
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+// As for bytes, but a character is only written once every byte of it
+// has been read.
+fn print_all_but_chars(
+    mut file: impl BufRead,
+    num: usize,
+    out: &mut impl Write,
+) -> MyResult<()> {
+    let mut ring: VecDeque<u8> = VecDeque::new();
+    let mut num_chars = 0;
+    loop {
+        let buf = file.fill_buf()?;
+        if buf.is_empty() {
+            break;
+        }
+        let len = buf.len();
+        num_chars += buf.iter().filter(|&&b| is_char_start(b)).count();
+        ring.extend(buf);
+        file.consume(len);
+
+        if num_chars > num {
+            let excess = num_chars - num;
+            let end = ring
+                .iter()
+                .enumerate()
+                .filter(|(_, &b)| is_char_start(b))
+                .nth(excess)
+                .map_or(ring.len(), |(i, _)| i);
+            write_front(&mut ring, end, out)?;
+            num_chars = num;
+        }
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+fn write_front(
+    ring: &mut VecDeque<u8>,
+    len: usize,
+    out: &mut impl Write,
+) -> MyResult<()> {
+    let (front, back) = ring.as_slices();
+    let from_front = len.min(front.len());
+    out.write_all(&front[..from_front])?;
+    out.write_all(&back[..len - from_front])?;
+    ring.drain(..len);
+    Ok(())
+}
+
+// --------------------------------------------------
+// A single byte, given literally or as an escape
+fn parse_separator(val: &str) -> Option<u8> {
+    match val.as_bytes() {
+        [byte] => Some(*byte),
+        br"\0" => Some(b'\0'),
+        br"\t" => Some(b'\t'),
+        br"\n" => Some(b'\n'),
+        br"\\" => Some(b'\\'),
+        _ => val
+            .strip_prefix("\\x")
+            .filter(|hex| hex.len() == 2)
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok()),
+    }
+}
+
+// --------------------------------------------------
+// A leading "-" means everything except the last so many.
+fn parse_count(val: &str) -> MyResult<Count> {
+    match val.strip_prefix('-') {
+        Some(num) => parse_positive_int(num)
+            .map(AllBut)
+            .map_err(|e| From::from(format!("-{}", e))),
+        None => parse_positive_int(val).map(First),
+    }
+}
+
+// --------------------------------------------------
+// Counts may carry a multiplier suffix such as "K" or "MiB".
 fn parse_positive_int(val: &str) -> MyResult<usize> {
-    match val.parse() {
-        Ok(n) if n > 0 => Ok(n),
+    match parse_size(val).map(usize::try_from) {
+        Ok(Ok(n)) if n > 0 => Ok(n),
+        Ok(Err(_)) | Err(SizeError::TooLarge) => {
+            Err(Error::Msg(format!("{}: {}", val, SizeError::TooLarge)))
+        }
         _ => Err(From::from(val)),
     }
 }
@@ -139,3 +500,124 @@ fn test_parse_positive_int() {
     assert!(res.is_err());
     assert_eq!(res.unwrap_err().to_string(), "0".to_string());
 }
+
+// --------------------------------------------------
+#[test]
+fn test_parse_count() {
+    assert_eq!(parse_count("3").unwrap(), First(3));
+    assert_eq!(parse_count("-3").unwrap(), AllBut(3));
+
+    let res = parse_count("-0");
+    assert_eq!(res.unwrap_err().to_string(), "-0");
+    let res = parse_count("--3");
+    assert_eq!(res.unwrap_err().to_string(), "--3");
+
+    // Multiplier suffixes
+    assert_eq!(parse_count("2b").unwrap(), First(1024));
+    assert_eq!(parse_count("1K").unwrap(), First(1024));
+    assert_eq!(parse_count("1KB").unwrap(), First(1000));
+    assert_eq!(parse_count("-1MiB").unwrap(), AllBut(1 << 20));
+    let res = parse_count("1X");
+    assert_eq!(res.unwrap_err().to_string(), "1X");
+
+    // Overflow is reported as such
+    let res = parse_count("-99999999999999999999");
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "-99999999999999999999: value too large"
+    );
+    let res = parse_count("64E");
+    assert_eq!(res.unwrap_err().to_string(), "64E: value too large");
+}
+
+// --------------------------------------------------
+#[test]
+fn test_parse_separator() {
+    assert_eq!(parse_separator(","), Some(b','));
+    assert_eq!(parse_separator("\\0"), Some(b'\0'));
+    assert_eq!(parse_separator("\\t"), Some(b'\t'));
+    assert_eq!(parse_separator("\\x1e"), Some(0x1e));
+    assert_eq!(parse_separator("\\xFF"), Some(0xff));
+    assert_eq!(parse_separator(""), None);
+    assert_eq!(parse_separator("ab"), None);
+    assert_eq!(parse_separator("é"), None);
+    assert_eq!(parse_separator("\\x1"), None);
+    assert_eq!(parse_separator("\\xzz"), None);
+}
+
+// --------------------------------------------------
+#[test]
+fn test_print_all_but() {
+    let text = "one\ntwo\nthree";
+    let mut out = vec![];
+    print_all_but_lines(io::Cursor::new(text), 1, b'\n', &mut out).unwrap();
+    assert_eq!(out, b"one\ntwo\n");
+
+    let mut out = vec![];
+    print_all_but_lines(io::Cursor::new(text), 5, b'\n', &mut out).unwrap();
+    assert!(out.is_empty());
+
+    let mut out = vec![];
+    print_all_but_lines(&b"a\0b\0c"[..], 2, b'\0', &mut out).unwrap();
+    assert_eq!(out, b"a\0");
+
+    // Read a byte at a time so the ring wraps around
+    let mut out = vec![];
+    let file = io::BufReader::with_capacity(1, text.as_bytes());
+    print_all_but_bytes(file, 4, &mut out).unwrap();
+    assert_eq!(out, b"one\ntwo\nt");
+}
+
+// --------------------------------------------------
+#[test]
+fn test_print_chars() {
+    let text = "Ö€x";
+    let mut out = vec![];
+    print_first_chars(io::Cursor::new(text), 2, &mut out).unwrap();
+    assert_eq!(out, "Ö€".as_bytes());
+
+    let mut out = vec![];
+    print_first_chars(io::Cursor::new(text), 9, &mut out).unwrap();
+    assert_eq!(out, text.as_bytes());
+
+    // Invalid UTF-8 is passed through as is
+    let text = b"a\xffb\xc3\x96";
+    let mut out = vec![];
+    print_first_chars(&text[..], 2, &mut out).unwrap();
+    assert_eq!(out, b"a\xff");
+
+    // Read a byte at a time so characters are split across reads
+    let text = "aÖ€bÖ";
+    let mut out = vec![];
+    let file = io::BufReader::with_capacity(1, text.as_bytes());
+    print_all_but_chars(file, 2, &mut out).unwrap();
+    assert_eq!(out, "aÖ€".as_bytes());
+
+    let mut out = vec![];
+    print_all_but_chars(text.as_bytes(), 5, &mut out).unwrap();
+    assert!(out.is_empty());
+}
+
+// --------------------------------------------------
+#[test]
+fn test_split_records() {
+    assert!(split_records(b"", b'\n').is_empty());
+    assert_eq!(split_records(b"\n", b'\n'), [""]);
+    assert_eq!(split_records(b"a\nb\n", b'\n'), ["a", "b"]);
+    assert_eq!(split_records(b"a\nb", b'\n'), ["a", "b"]);
+    assert_eq!(split_records(b"a\n\0b\0", b'\0'), ["a\n", "b"]);
+    assert_eq!(split_records(b"\xff\n", b'\n'), ["\u{fffd}"]);
+}
+
+// --------------------------------------------------
+#[test]
+fn test_run_with() {
+    let args = ["headr", "-n", "2", "-", "no-such-file"];
+    let config = get_args_from(args.iter().map(OsString::from)).unwrap();
+    let stdin = io::Cursor::new("one\ntwo\nthree\n");
+    let (mut stdout, mut stderr) = (vec![], vec![]);
+    let res = run_with(config, stdin, &mut stdout, &mut stderr);
+    assert_eq!(res.unwrap_err().exit_code(), 1);
+    assert_eq!(stdout, b"==> - <==\none\ntwo\n");
+    assert!(stderr.starts_with(b"headr: no-such-file: "));
+}
@@ -1,11 +1,9 @@
 use assert_cmd::Command;
+#[cfg(target_os = "linux")]
+use clr_io::test_support::{unreadable_error, UNREADABLE};
 use predicates::prelude::*;
 use rand::{distributions::Alphanumeric, Rng};
-use std::io::prelude::*;
-use std::{
-    error::Error,
-    fs::{self, File},
-};
+use std::{error::Error, fs};
 
 type TestResult = Result<(), Box<dyn Error>>;
 
@@ -15,6 +13,7 @@ const ONE: &str = "./tests/inputs/one.txt";
 const TWO: &str = "./tests/inputs/two.txt";
 const THREE: &str = "./tests/inputs/three.txt";
 const TEN: &str = "./tests/inputs/ten.txt";
+const BINARY: &str = "./tests/inputs/binary.bin";
 
 // --------------------------------------------------
 fn random_string() -> String {
@@ -41,7 +40,7 @@ fn dies_bad_bytes() -> TestResult {
     let bad = random_string();
     let expected = format!("illegal byte count -- {}", &bad);
     Command::cargo_bin(PRG)?
-        .args(&["-c", &bad, EMPTY])
+        .args(["-c", &bad, EMPTY])
         .assert()
         .failure()
         .stderr(predicate::str::contains(expected));
@@ -55,7 +54,7 @@ fn dies_bad_lines() -> TestResult {
     let bad = random_string();
     let expected = format!("illegal line count -- {}", &bad);
     Command::cargo_bin(PRG)?
-        .args(&["-n", &bad, EMPTY])
+        .args(["-n", &bad, EMPTY])
         .assert()
         .failure()
         .stderr(predicate::str::contains(expected));
@@ -70,7 +69,7 @@ fn dies_bytes_and_lines() -> TestResult {
                used with '--bytes <BYTES>'";
 
     Command::cargo_bin(PRG)?
-        .args(&["-n", "1", "-c", "2"])
+        .args(["-n", "1", "-c", "2"])
         .assert()
         .failure()
         .stderr(predicate::str::contains(msg));
@@ -92,18 +91,37 @@ fn skips_bad_file() -> TestResult {
 }
 
 // --------------------------------------------------
-fn run(args: &[&str], expected_file: &str) -> TestResult {
-    // Extra work here due to lossy UTF
-    let mut file = File::open(expected_file)?;
-    let mut buffer = Vec::new();
-    file.read_to_end(&mut buffer)?;
-    let expected = String::from_utf8_lossy(&buffer);
+#[test]
+#[cfg(target_os = "linux")]
+fn skips_unreadable_file() -> TestResult {
+    let one = fs::read_to_string(ONE)?;
+    let chopped = one.trim_end();
+    for (args, stdout) in [
+        (&[][..], one.as_str()),
+        (&["-c", "100"], &one),
+        (&["-n", "-1"], ""),
+        (&["-c", "-1"], chopped),
+        (&["--chars", "-1"], chopped),
+    ] {
+        Command::cargo_bin(PRG)?
+            .args(args)
+            .args(["-q", UNREADABLE, ONE])
+            .assert()
+            .failure()
+            .stderr(predicate::str::starts_with(unreadable_error(PRG)))
+            .stdout(stdout.to_string());
+    }
+    Ok(())
+}
 
+// --------------------------------------------------
+fn run(args: &[&str], expected_file: &str) -> TestResult {
+    let expected = fs::read(expected_file)?;
     Command::cargo_bin(PRG)?
         .args(args)
         .assert()
         .success()
-        .stdout(predicate::eq(&expected.as_bytes() as &[u8]));
+        .stdout(expected);
 
     Ok(())
 }
@@ -114,18 +132,14 @@ fn run_stdin(
     input_file: &str,
     expected_file: &str,
 ) -> TestResult {
-    // Extra work here due to lossy UTF
-    let mut file = File::open(expected_file)?;
-    let mut buffer = Vec::new();
-    file.read_to_end(&mut buffer)?;
-    let expected = String::from_utf8_lossy(&buffer);
-    let input = fs::read_to_string(input_file)?;
+    let expected = fs::read(expected_file)?;
+    let input = fs::read(input_file)?;
 
     Command::cargo_bin(PRG)?
         .write_stdin(input)
         .args(args)
         .assert()
-        .stdout(predicate::eq(&expected.as_bytes() as &[u8]));
+        .stdout(expected);
 
     Ok(())
 }
@@ -419,3 +433,297 @@ fn multiple_files_c4() -> TestResult {
         "tests/expected/all.c4.out",
     )
 }
+
+// --------------------------------------------------
+#[test]
+fn empty_n_minus2() -> TestResult {
+    run(&[EMPTY, "-n", "-2"], "tests/expected/empty.txt.n-2.out")
+}
+
+#[test]
+fn one_n_minus2() -> TestResult {
+    run(&[ONE, "-n", "-2"], "tests/expected/one.txt.n-2.out")
+}
+
+#[test]
+fn one_c_minus4() -> TestResult {
+    run(&[ONE, "-c", "-4"], "tests/expected/one.txt.c-4.out")
+}
+
+#[test]
+fn three_n_minus2() -> TestResult {
+    run(&[THREE, "-n-2"], "tests/expected/three.txt.n-2.out")
+}
+
+#[test]
+fn three_c_minus4_stdin() -> TestResult {
+    run_stdin(&["-c", "-4"], THREE, "tests/expected/three.txt.c-4.out")
+}
+
+#[test]
+fn ten_n_minus2() -> TestResult {
+    run(&[TEN, "--lines=-2"], "tests/expected/ten.txt.n-2.out")
+}
+
+#[test]
+fn ten_n_minus2_stdin() -> TestResult {
+    run_stdin(&["-n", "-2"], TEN, "tests/expected/ten.txt.n-2.out")
+}
+
+#[test]
+fn ten_c_minus4() -> TestResult {
+    run(&[TEN, "-c", "-4"], "tests/expected/ten.txt.c-4.out")
+}
+
+#[test]
+fn multiple_files_n_minus2() -> TestResult {
+    run(
+        &["-n", "-2", EMPTY, ONE, TWO, THREE, TEN],
+        "tests/expected/all.n-2.out",
+    )
+}
+
+#[test]
+fn multiple_files_c_minus4() -> TestResult {
+    run(
+        &[EMPTY, ONE, TWO, THREE, TEN, "-c", "-4"],
+        "tests/expected/all.c-4.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn multiple_files_c1k() -> TestResult {
+    run(
+        &["-c", "1K", EMPTY, ONE, TWO, THREE, TEN],
+        "tests/expected/all.c1K.out",
+    )
+}
+
+#[test]
+fn multiple_files_n1kib() -> TestResult {
+    run(
+        &["--lines=1KiB", EMPTY, ONE, TWO, THREE, TEN],
+        "tests/expected/all.n1KiB.out",
+    )
+}
+
+#[test]
+fn multiple_files_c_minus1b() -> TestResult {
+    run(
+        &["-c", "-1b", EMPTY, ONE, TWO, THREE, TEN],
+        "tests/expected/all.c-1b.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_count_too_large() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-c", "99999999999999999999", EMPTY])
+        .assert()
+        .failure()
+        .stderr(
+            "headr: illegal byte count -- \
+             99999999999999999999: value too large\n",
+        );
+
+    Command::cargo_bin(PRG)?
+        .args(["-n", "-64E", EMPTY])
+        .assert()
+        .failure()
+        .stderr("headr: illegal line count -- -64E: value too large\n");
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn all_but_huge_count() -> TestResult {
+    // Nothing is allocated up front for the held-back lines or bytes
+    for count in [
+        ["-n", "-18446744073709551615"],
+        ["-n", "-1T"],
+        ["-c", "-18446744073709551615"],
+        ["-c", "-1T"],
+        ["--chars", "-1T"],
+    ] {
+        Command::cargo_bin(PRG)?
+            .args(count)
+            .arg(TEN)
+            .assert()
+            .success()
+            .stdout("");
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn binary_bytes() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-c", "5", BINARY])
+        .assert()
+        .success()
+        .stdout(&b"\x1f\x8b\x08\x00\xc3"[..]);
+    Ok(())
+}
+
+#[test]
+fn binary_lines() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-n", "2", BINARY])
+        .assert()
+        .success()
+        .stdout(&b"\x1f\x8b\x08\x00\xc3\n\xff\xfe\x00\n"[..]);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn one_chars() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--chars", "2", ONE])
+        .assert()
+        .success()
+        .stdout("Ön");
+    Ok(())
+}
+
+#[test]
+fn one_chars_minus() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--chars=-9", ONE])
+        .assert()
+        .success()
+        .stdout("Öne line, fou");
+    Ok(())
+}
+
+#[test]
+fn dies_chars_and_bytes() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--chars", "1", "-c", "1", ONE])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "The argument '--chars <CHARS>' cannot be used with",
+        ));
+    Ok(())
+}
+
+#[test]
+fn dies_bad_chars() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--chars", "0", ONE])
+        .assert()
+        .failure()
+        .stderr("headr: illegal character count -- 0\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn zero_terminated() -> TestResult {
+    for flag in ["-z", "--zero-terminated"] {
+        Command::cargo_bin(PRG)?
+            .args([flag, "-n", "2"])
+            .write_stdin("a\nb\0c\0d\0")
+            .assert()
+            .success()
+            .stdout("a\nb\0c\0");
+    }
+    Ok(())
+}
+
+#[test]
+fn zero_terminated_minus() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-z", "-n", "-1"])
+        .write_stdin("a\0b\0c")
+        .assert()
+        .success()
+        .stdout("a\0b\0");
+    Ok(())
+}
+
+#[test]
+fn separator() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--separator", ",", "-n", "2"])
+        .write_stdin("a,b,c\n")
+        .assert()
+        .success()
+        .stdout("a,b,");
+
+    Command::cargo_bin(PRG)?
+        .args(["--separator=\\x1e", "-n", "1"])
+        .write_stdin("a\nb\x1ec")
+        .assert()
+        .success()
+        .stdout("a\nb\x1e");
+    Ok(())
+}
+
+#[test]
+fn dies_bad_separator() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--separator", "ab", ONE])
+        .assert()
+        .failure()
+        .stderr("headr: illegal separator -- ab\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn one_verbose() -> TestResult {
+    run(&["-v", ONE], "tests/expected/one.txt.v.out")?;
+    run(&["--verbose", ONE], "tests/expected/one.txt.v.out")
+}
+
+#[test]
+fn multiple_files_quiet() -> TestResult {
+    let expected = "tests/expected/all.q.n2.out";
+    run(&["-q", "-n", "2", EMPTY, ONE, TWO, THREE, TEN], expected)?;
+    run(&["--silent", "-n", "2", EMPTY, ONE, TWO, THREE, TEN], expected)?;
+    // The last of -v and -q wins
+    run(&["-v", "-q", "-n", "2", EMPTY, ONE, TWO, THREE, TEN], expected)
+}
+
+// --------------------------------------------------
+#[test]
+fn json() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--json", "-n", "2", EMPTY, TWO, "-"])
+        .write_stdin("a\n\"b\"\n")
+        .assert()
+        .success()
+        .stdout(format!(
+            "{}\n{}\n{}\n",
+            r#"{"file":"./tests/inputs/empty.txt","lines":[]}"#,
+            concat!(
+                r#"{"file":"./tests/inputs/two.txt","#,
+                r#""lines":["Two lines.","Four words."]}"#
+            ),
+            r#"{"file":"-","lines":["a","\"b\""]}"#,
+        ));
+    Ok(())
+}
+
+#[test]
+fn json_missing_file() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--json", "-n", "1", "./tests/inputs/blargh", ONE])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("headr: ./tests/inputs/blargh: "))
+        .stdout(format!(
+            "{}\n",
+            concat!(
+                r#"{"file":"./tests/inputs/one.txt","#,
+                r#""lines":["Öne line, four words."]}"#
+            ),
+        ));
+    Ok(())
+}
@@ -0,0 +1,59 @@
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use std::{env, ffi::OsString, fs, io, path::PathBuf};
+use tempfile::TempDir;
+
+// Lines of biggie text per input file; set WCR_BENCH_LINES to count
+// something closer to real logs.
+const LINES: usize = 100_000;
+const NUM_FILES: usize = 4;
+
+// --------------------------------------------------
+fn make_inputs(dir: &TempDir) -> Vec<PathBuf> {
+    let lines = env::var("WCR_BENCH_LINES")
+        .ok()
+        .and_then(|val| val.parse().ok())
+        .unwrap_or(LINES);
+
+    (0..NUM_FILES)
+        .map(|i| {
+            let path = dir.path().join(format!("biggie{}.txt", i));
+            let mut text = vec![];
+            biggie::write_lines(&mut text, lines).unwrap();
+            fs::write(&path, text).unwrap();
+            path
+        })
+        .collect()
+}
+
+// --------------------------------------------------
+fn run(flags: &str, files: &[PathBuf]) {
+    let args = [OsString::from("wcr"), OsString::from(flags)]
+        .into_iter()
+        .chain(files.iter().map(OsString::from));
+    let config = wcr::get_args_from(args).unwrap();
+    wcr::run_with(config, io::empty(), io::sink(), io::sink()).unwrap();
+}
+
+// --------------------------------------------------
+fn bench(c: &mut Criterion) {
+    let dir = tempfile::tempdir().unwrap();
+    let files = make_inputs(&dir);
+    let size = fs::metadata(&files[0]).unwrap().len();
+
+    let mut group = c.benchmark_group("one_file");
+    group.throughput(Throughput::Bytes(size));
+    for flags in ["-l", "-lwc", "-m", "-L"] {
+        group.bench_function(flags, |b| b.iter(|| run(flags, &files[..1])));
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("all_files");
+    group.throughput(Throughput::Bytes(size * NUM_FILES as u64));
+    for flags in ["-l", "-lwc"] {
+        group.bench_function(flags, |b| b.iter(|| run(flags, &files)));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
@@ -4,6 +4,9 @@ ROOT="tests/inputs"
 FILES="$ROOT/empty.txt $ROOT/fox.txt $ROOT/atlamal.txt"
 OUT_DIR="tests/expected"
 
+# wcr pads every column to the width of the largest number shown, while
+# GNU wc goes by the file sizes, so the padding may need trimming.
+
 [[ ! -d "$OUT_DIR" ]] && mkdir -p "$OUT_DIR"
 
 for FILE in $FILES; do
@@ -19,6 +22,8 @@ for FILE in $FILES; do
     wc -wl  $FILE > ${OUT_DIR}/${BASENAME}.wl.out
     wc -cl  $FILE > ${OUT_DIR}/${BASENAME}.cl.out
     wc -ml  $FILE > ${OUT_DIR}/${BASENAME}.ml.out
+    LC_ALL=C.UTF-8 wc -L  $FILE > ${OUT_DIR}/${BASENAME}.L.out
+    LC_ALL=C.UTF-8 wc -lL $FILE > ${OUT_DIR}/${BASENAME}.lL.out
 done
 
 wc < "$ROOT/atlamal.txt" > "$OUT_DIR/atlamal.txt.stdin.out"
@@ -34,3 +39,7 @@ wc -wm  $FILES > $OUT_DIR/all.wm.out
 wc -wl  $FILES > $OUT_DIR/all.wl.out
 wc -cl  $FILES > $OUT_DIR/all.cl.out
 wc -ml  $FILES > $OUT_DIR/all.ml.out
+
+# Display widths depend on the locale
+LC_ALL=C.UTF-8 wc -L $FILES > $OUT_DIR/all.L.out
+LC_ALL=C.UTF-8 wc -L "$ROOT/wide.txt" > "$OUT_DIR/wide.txt.L.out"
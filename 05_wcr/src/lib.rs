@@ -1,29 +1,109 @@
 use clap::{Command, Arg};
-use std::error::Error;
-use std::fs::File;
-use std::io::{self, BufRead, BufReader};
+use clr_io::Error;
+use memchr::memchr_iter;
+use rayon::prelude::*;
+use serde::Serialize;
+use std::{
+    env,
+    ffi::OsString,
+    io::{self, BufRead, BufReader, Write},
+    mem, str,
+};
+use unicode_width::UnicodeWidthChar;
 
-type MyResult<T> = Result<T, Box<dyn Error>>;
+type MyResult<T> = clr_io::Result<T>;
+
+const PRG: &str = "wcr";
+
+// Large reads keep the fast paths busy on big files
+const BUF_SIZE: usize = 256 * 1024;
 
 #[derive(Debug)]
 pub struct Config {
     files: Vec<String>,
+    files0_from: Option<String>,
     lines: bool,
     words: bool,
     bytes: bool,
     chars: bool,
+    max_line_length: bool,
+    total: Total,
+    format: Format,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Default, PartialEq)]
 pub struct FileInfo {
     num_lines: usize,
     num_words: usize,
     num_bytes: usize,
     num_chars: usize,
+    num_max_line_len: usize,
+}
+
+impl FileInfo {
+    // Fold another file into a total
+    fn add(&mut self, other: &FileInfo) {
+        self.num_lines += other.num_lines;
+        self.num_words += other.num_words;
+        self.num_bytes += other.num_bytes;
+        self.num_chars += other.num_chars;
+        self.num_max_line_len =
+            self.num_max_line_len.max(other.num_max_line_len);
+    }
+}
+
+// One row of the machine-readable formats, which have every count
+#[derive(Serialize)]
+struct Record<'a> {
+    // None for the total
+    file: Option<&'a str>,
+    lines: usize,
+    words: usize,
+    bytes: usize,
+    chars: usize,
+    max_line_length: usize,
+}
+
+impl<'a> Record<'a> {
+    fn new(file: Option<&'a str>, info: &FileInfo) -> Self {
+        Record {
+            file,
+            lines: info.num_lines,
+            words: info.num_words,
+            bytes: info.num_bytes,
+            chars: info.num_chars,
+            max_line_length: info.num_max_line_len,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Format {
+    Text,
+    Json,
+    Csv,
+    Tsv,
+}
+
+// When to print the line of totals
+#[derive(Debug, PartialEq)]
+enum Total {
+    Auto,
+    Always,
+    Only,
+    Never,
 }
 
 // --------------------------------------------------
 pub fn get_args() -> MyResult<Config> {
+    get_args_from(env::args_os())
+}
+
+// --------------------------------------------------
+pub fn get_args_from<I>(args: I) -> MyResult<Config>
+where
+    I: IntoIterator<Item = OsString>,
+{
     let matches = Command::new("wcr")
         .version("0.1.0")
         .author("Ken Youens-Clark <kyclark@gmail.com>")
@@ -36,6 +116,29 @@ pub fn get_args() -> MyResult<Config> {
                 .default_value("-")
                 .multiple_occurrences(true),
         )
+        .arg(
+            Arg::new("total")
+                .long("total")
+                .value_name("WHEN")
+                .possible_values(["auto", "always", "only", "never"])
+                .default_value("auto")
+                .help("When to print a line of totals"),
+        )
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .value_name("FORMAT")
+                .possible_values(["text", "json", "csv", "tsv"])
+                .default_value("text")
+                .help("Output format; json, csv and tsv show every count"),
+        )
+        .arg(
+            Arg::new("files0_from")
+                .long("files0-from")
+                .value_name("F")
+                .help("Read NUL-terminated file names from F (- for stdin)")
+                .conflicts_with("files"),
+        )
         .arg(
             Arg::new("words")
                 .short('w')
@@ -65,14 +168,25 @@ pub fn get_args() -> MyResult<Config> {
                 .help("Show line count")
                 .takes_value(false),
         )
-        .get_matches();
+        .arg(
+            Arg::new("max_line_length")
+                .short('L')
+                .long("max-line-length")
+                .help("Show maximum display width of a line")
+                .takes_value(false),
+        )
+        .try_get_matches_from(args)?;
 
     let mut lines = matches.is_present("lines");
     let mut words = matches.is_present("words");
     let mut bytes = matches.is_present("bytes");
     let chars = matches.is_present("chars");
+    let max_line_length = matches.is_present("max_line_length");
 
-    if [words, bytes, chars, lines].iter().all(|v| v == &false) {
+    if [words, bytes, chars, lines, max_line_length]
+        .iter()
+        .all(|v| v == &false)
+    {
         lines = true;
         words = true;
         bytes = true;
@@ -80,110 +194,433 @@ pub fn get_args() -> MyResult<Config> {
 
     Ok(Config {
         files: matches.values_of_lossy("files").unwrap(),
+        files0_from: matches.value_of("files0_from").map(String::from),
         lines,
         words,
         bytes,
         chars,
+        max_line_length,
+        total: match matches.value_of("total") {
+            Some("always") => Total::Always,
+            Some("only") => Total::Only,
+            Some("never") => Total::Never,
+            _ => Total::Auto,
+        },
+        format: match matches.value_of("format") {
+            Some("json") => Format::Json,
+            Some("csv") => Format::Csv,
+            Some("tsv") => Format::Tsv,
+            _ => Format::Text,
+        },
     })
 }
 
 // --------------------------------------------------
 pub fn run(config: Config) -> MyResult<()> {
-    let mut total_lines = 0;
-    let mut total_words = 0;
-    let mut total_bytes = 0;
-    let mut total_chars = 0;
-
-    for filename in &config.files {
-        match open(filename) {
-            Err(err) => eprintln!("{}: {}", filename, err),
-            Ok(file) => {
-                if let Ok(info) = count(file) {
-                    println!(
-                        "{}{}{}{}{}",
-                        format_field(info.num_lines, config.lines),
-                        format_field(info.num_words, config.words),
-                        format_field(info.num_bytes, config.bytes),
-                        format_field(info.num_chars, config.chars),
-                        if filename == "-" {
-                            "".to_string()
-                        } else {
-                            format!(" {}", &filename)
-                        },
-                    );
-
-                    total_lines += info.num_lines;
-                    total_words += info.num_words;
-                    total_bytes += info.num_bytes;
-                    total_chars += info.num_chars;
+    run_with(
+        config,
+        io::stdin().lock(),
+        io::stdout().lock(),
+        io::stderr(),
+    )
+}
+
+// --------------------------------------------------
+pub fn run_with(
+    config: Config,
+    mut stdin: impl BufRead,
+    mut stdout: impl Write,
+    mut stderr: impl Write,
+) -> MyResult<()> {
+    let mut failed = false;
+
+    let listed;
+    let files = match &config.files0_from {
+        Some(list) => {
+            let (names, ok) = read_files0(list, &mut stdin, &mut stderr)?;
+            failed |= !ok;
+            listed = names;
+            &listed
+        }
+        None => &config.files,
+    };
+
+    // Count named files on the thread pool up front; stdin can only be
+    // read here, in order, and output keeps the order of the arguments.
+    let counts: Vec<_> = files
+        .par_iter()
+        .map(|filename| {
+            (filename != "-")
+                .then(|| count_file(filename, io::empty(), &config))
+        })
+        .collect();
+
+    let mut rows = vec![];
+    let mut total = FileInfo::default();
+    for (filename, res) in files.iter().zip(counts) {
+        let res =
+            res.unwrap_or_else(|| count_file(filename, &mut stdin, &config));
+        match res {
+            Err(err) => {
+                clr_io::report_to(&mut stderr, PRG, &err);
+                failed = true;
+            }
+            Ok(info) => {
+                total.add(&info);
+                if config.total != Total::Only {
+                    rows.push((Some(filename.as_str()), info));
                 }
             }
         }
     }
 
-    if config.files.len() > 1 {
-        println!(
-            "{}{}{}{} total",
-            format_field(total_lines, config.lines),
-            format_field(total_words, config.words),
-            format_field(total_bytes, config.bytes),
-            format_field(total_chars, config.chars)
-        );
+    // The total is the row without a file name
+    match config.total {
+        Total::Auto if files.len() > 1 => rows.push((None, total)),
+        Total::Always | Total::Only => rows.push((None, total)),
+        _ => {}
     }
 
+    match config.format {
+        Format::Text => print_table(&rows, &config, &mut stdout)?,
+        Format::Json => {
+            for (name, info) in &rows {
+                serde_json::to_writer(&mut stdout, &Record::new(*name, info))
+                    .map_err(io::Error::from)?;
+                writeln!(stdout)?;
+            }
+        }
+        Format::Csv | Format::Tsv => {
+            let delimiter = if config.format == Format::Csv {
+                b','
+            } else {
+                b'\t'
+            };
+            let mut writer = csv::WriterBuilder::new()
+                .delimiter(delimiter)
+                .from_writer(&mut stdout);
+            for (name, info) in &rows {
+                writer
+                    .serialize(Record::new(*name, info))
+                    .map_err(io::Error::from)?;
+            }
+            writer.flush()?;
+        }
+    }
+
+    if failed {
+        return Err(Error::Exit(1));
+    }
     Ok(())
 }
 
 // --------------------------------------------------
-fn open(filename: &str) -> MyResult<Box<dyn BufRead>> {
-    match filename {
-        "-" => Ok(Box::new(BufReader::new(io::stdin()))),
-        _ => Ok(Box::new(BufReader::new(File::open(filename)?))),
+fn print_table(
+    rows: &[(Option<&str>, FileInfo)],
+    config: &Config,
+    out: &mut impl Write,
+) -> MyResult<()> {
+    // Every column is as wide as the largest number shown
+    let width = rows
+        .iter()
+        .flat_map(|(_, info)| columns(info, config))
+        .max()
+        .map_or(1, |max| max.to_string().len());
+
+    for (name, info) in rows {
+        let counts = format_counts(info, config, width);
+        let label = match name {
+            Some("-") => None,
+            Some(name) => Some(*name),
+            None if config.total == Total::Only => None,
+            None => Some("total"),
+        };
+        match label {
+            Some(label) => writeln!(out, "{} {}", counts, label)?,
+            None => writeln!(out, "{}", counts)?,
+        }
     }
+    Ok(())
 }
 
 // --------------------------------------------------
-fn format_field(value: usize, show: bool) -> String {
-    if show {
-        format!("{:>8}", value)
-    } else {
-        "".to_string()
+// The counts selected for output, in column order
+fn columns(info: &FileInfo, config: &Config) -> Vec<usize> {
+    [
+        (info.num_lines, config.lines),
+        (info.num_words, config.words),
+        (info.num_bytes, config.bytes),
+        (info.num_chars, config.chars),
+        (info.num_max_line_len, config.max_line_length),
+    ]
+    .into_iter()
+    .filter(|(_, show)| *show)
+    .map(|(value, _)| value)
+    .collect()
+}
+
+// --------------------------------------------------
+fn format_counts(info: &FileInfo, config: &Config, width: usize) -> String {
+    columns(info, config)
+        .iter()
+        .map(|value| format!("{:>1$}", value, width))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// --------------------------------------------------
+// The NUL-terminated names in `list`. Names that can't be opened are
+// reported and skipped, with false returned alongside the rest.
+fn read_files0(
+    list: &str,
+    stdin: impl BufRead,
+    stderr: &mut impl Write,
+) -> MyResult<(Vec<String>, bool)> {
+    let mut file = clr_io::open_with(list, stdin)?;
+    let mut names = vec![];
+    let mut ok = true;
+    let mut buf = vec![];
+    for num in 1.. {
+        buf.clear();
+        let bytes = file
+            .read_until(b'\0', &mut buf)
+            .map_err(|e| Error::file(list, e))?;
+        if bytes == 0 {
+            break;
+        }
+        let name = buf.strip_suffix(b"\0").unwrap_or(&buf);
+        let err = if name.is_empty() {
+            format!("{}:{}: invalid zero-length file name", list, num)
+        } else if name == b"-" && list == "-" {
+            "when reading file names from standard input, \
+             no file name of '-' allowed"
+                .to_string()
+        } else {
+            names.push(String::from_utf8_lossy(name).into_owned());
+            continue;
+        };
+        clr_io::report_to(stderr, PRG, &Error::Msg(err));
+        ok = false;
     }
+    Ok((names, ok))
 }
 
 // --------------------------------------------------
-pub fn count(mut file: impl BufRead) -> MyResult<FileInfo> {
-    let mut num_lines = 0;
-    let mut num_words = 0;
-    let mut num_bytes = 0;
-    let mut num_chars = 0;
-    let mut line = String::new();
+// Only what was asked for is counted, so that e.g. -l never has to look
+// past the newlines.
+fn count_file(
+    filename: &str,
+    stdin: impl BufRead,
+    config: &Config,
+) -> MyResult<FileInfo> {
+    let input = clr_io::open_input_with(filename, stdin)?;
+    let file = BufReader::with_capacity(BUF_SIZE, input);
+    let counter = if config.format == Format::Text {
+        Counter::new(config.words || config.chars, config.max_line_length)
+    } else {
+        Counter::new(true, true)
+    };
+    count_with(file, counter).map_err(|e| Error::file(filename, e))
+}
+
+// --------------------------------------------------
+pub fn count(file: impl BufRead) -> io::Result<FileInfo> {
+    count_with(file, Counter::new(true, true))
+}
 
+// --------------------------------------------------
+// Count in whatever chunks the reader hands back, so input need not be
+// valid UTF-8 or split into lines.
+fn count_with(
+    mut file: impl BufRead,
+    mut counter: Counter,
+) -> io::Result<FileInfo> {
     loop {
-        let line_bytes = file.read_line(&mut line)?;
-        if line_bytes == 0 {
+        let buf = match file.fill_buf() {
+            Ok(buf) => buf,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        if buf.is_empty() {
             break;
         }
-        num_bytes += line_bytes;
-        num_lines += 1;
-        num_words += line.split_whitespace().count();
-        num_chars += line.chars().count();
-        line.clear();
+        counter.update(buf);
+        let len = buf.len();
+        file.consume(len);
     }
+    Ok(counter.finish())
+}
 
-    Ok(FileInfo {
-        num_lines,
-        num_words,
-        num_bytes,
-        num_chars,
-    })
+// --------------------------------------------------
+#[derive(Debug, Default)]
+struct Counter {
+    info: FileInfo,
+    // Whether characters must be looked at, and whether their widths
+    scan: bool,
+    widths: bool,
+    in_word: bool,
+    line_pos: usize,
+    // The start of a character cut off at the end of the last chunk
+    partial: Vec<u8>,
+}
+
+impl Counter {
+    fn new(words_or_chars: bool, widths: bool) -> Self {
+        Counter {
+            scan: words_or_chars || widths,
+            widths,
+            ..Default::default()
+        }
+    }
+
+    fn update(&mut self, buf: &[u8]) {
+        self.info.num_bytes += buf.len();
+        if !self.scan {
+            self.info.num_lines += memchr_iter(b'\n', buf).count();
+        } else if self.partial.is_empty() && buf.is_ascii() {
+            self.update_ascii(buf);
+        } else {
+            self.update_utf8(buf);
+        }
+    }
+
+    // Every byte is a character, so there is nothing to decode.
+    fn update_ascii(&mut self, buf: &[u8]) {
+        let info = &mut self.info;
+        info.num_chars += buf.len();
+        info.num_lines += memchr_iter(b'\n', buf).count();
+
+        // A word starts wherever a space is followed by anything else
+        if !self.in_word && !is_space(buf[0]) {
+            info.num_words += 1;
+        }
+        info.num_words += count_word_starts(buf);
+        self.in_word = !is_space(buf[buf.len() - 1]);
+
+        if self.widths {
+            buf.iter().for_each(|&b| self.advance(char::from(b)));
+        }
+    }
+
+    fn update_utf8(&mut self, buf: &[u8]) {
+        let mut joined;
+        let mut input = buf;
+        if !self.partial.is_empty() {
+            joined = mem::take(&mut self.partial);
+            joined.extend_from_slice(buf);
+            input = &joined;
+        }
+
+        // Invalid sequences count as one U+FFFD each, as they would in
+        // String::from_utf8_lossy.
+        loop {
+            match str::from_utf8(input) {
+                Ok(valid) => {
+                    valid.chars().for_each(|c| self.add_char(c));
+                    break;
+                }
+                Err(e) => {
+                    let (valid, rest) = input.split_at(e.valid_up_to());
+                    // from_utf8 has just vouched for this prefix
+                    let valid = str::from_utf8(valid).unwrap_or_default();
+                    valid.chars().for_each(|c| self.add_char(c));
+                    match e.error_len() {
+                        Some(len) => {
+                            self.add_char(char::REPLACEMENT_CHARACTER);
+                            input = &rest[len..];
+                        }
+                        None => {
+                            self.partial = rest.to_vec();
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn add_char(&mut self, c: char) {
+        let info = &mut self.info;
+        info.num_chars += 1;
+
+        if c == '\n' {
+            info.num_lines += 1;
+        }
+
+        if c.is_whitespace() {
+            self.in_word = false;
+        } else if !self.in_word {
+            self.in_word = true;
+            info.num_words += 1;
+        }
+
+        if self.widths {
+            self.advance(c);
+        }
+    }
+
+    // Line width in terminal columns, as GNU wc -L measures it: tabs
+    // stop every 8 columns, East Asian wide characters take 2, control
+    // characters none, and a CR or FF starts over.
+    fn advance(&mut self, c: char) {
+        match c {
+            '\n' | '\r' | '\x0c' => {
+                self.info.num_max_line_len =
+                    self.info.num_max_line_len.max(self.line_pos);
+                self.line_pos = 0;
+            }
+            '\t' => self.line_pos += 8 - self.line_pos % 8,
+            _ => self.line_pos += c.width().unwrap_or(0),
+        }
+    }
+
+    fn finish(mut self) -> FileInfo {
+        // A truncated character at the very end
+        if !self.partial.is_empty() {
+            self.add_char(char::REPLACEMENT_CHARACTER);
+        }
+        let info = &mut self.info;
+        info.num_max_line_len = info.num_max_line_len.max(self.line_pos);
+        self.info
+    }
+}
+
+// --------------------------------------------------
+// Count the spaces followed by a non-space. The pairs are tallied in u8s,
+// a block at a time, with no branches so that the loop vectorizes.
+fn count_word_starts(buf: &[u8]) -> usize {
+    let (firsts, seconds) = (&buf[..buf.len() - 1], &buf[1..]);
+    firsts
+        .chunks(255)
+        .zip(seconds.chunks(255))
+        .map(|(firsts, seconds)| {
+            let pairs = firsts.iter().zip(seconds);
+            let starts = pairs.fold(0u8, |n, (&a, &b)| {
+                n + u8::from(is_space(a) & !is_space(b))
+            });
+            usize::from(starts)
+        })
+        .sum()
+}
+
+// --------------------------------------------------
+// The ASCII characters char::is_whitespace accepts: space, \t, \n, \v,
+// \f and \r.
+fn is_space(b: u8) -> bool {
+    (b == b' ') | (b.wrapping_sub(b'\t') < 5)
 }
 
 // --------------------------------------------------
 #[cfg(test)]
 mod tests {
-    use super::{count, format_field, FileInfo};
-    use std::io::Cursor;
+    use super::{
+        count, count_with, format_counts, get_args_from, run_with, Counter,
+        FileInfo,
+    };
+    use std::{
+        ffi::OsString,
+        io::{self, BufReader, Cursor, Read},
+    };
 
     #[test]
     fn test_count() {
@@ -195,14 +632,115 @@ mod tests {
             num_words: 10,
             num_chars: 48,
             num_bytes: 48,
+            num_max_line_len: 46,
         };
         assert_eq!(info.unwrap(), expected);
     }
 
+    fn max_line_len(text: &str) -> usize {
+        count(Cursor::new(text)).unwrap().num_max_line_len
+    }
+
+    #[test]
+    fn test_max_line_len() {
+        assert_eq!(max_line_len(""), 0);
+        assert_eq!(max_line_len("abc\n"), 3);
+        assert_eq!(max_line_len("a\tb"), 9);
+        assert_eq!(max_line_len("\t\t"), 16);
+        assert_eq!(max_line_len("1234567\tx"), 9);
+        assert_eq!(max_line_len("日本語x"), 7);
+        assert_eq!(max_line_len("abcdef\rxy"), 6);
+        assert_eq!(max_line_len("a\x01b"), 2);
+        assert_eq!(max_line_len("ab\nabcd\nabc"), 4);
+    }
+
+    #[test]
+    fn test_count_invalid_utf8() {
+        // Read a byte at a time so characters are split across reads
+        let text = b"caf\xc3\xa9 \xff\xfe ok\n\xe2\x82";
+        let info = count(BufReader::with_capacity(1, &text[..])).unwrap();
+        let expected = FileInfo {
+            num_lines: 1,
+            num_words: 4,
+            num_bytes: 14,
+            num_chars: 12,
+            num_max_line_len: 10,
+        };
+        assert_eq!(info, expected);
+        assert_eq!(count(&text[..]).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_count_chunks() {
+        // The ASCII fast path and the UTF-8 path must agree wherever the
+        // chunk boundaries fall.
+        let texts: [&[u8]; 5] = [
+            b"one two\tthree\n  four\x0bfive\r\n\n six",
+            b" lead and trail \n",
+            "Öne line,\tfour wörds.\n日本語 x\n".as_bytes(),
+            b"a\xffb \xc3\n\xe6\x97\xa5\xe6",
+            b"",
+        ];
+        for text in texts {
+            let expected = count(text).unwrap();
+            for size in 1..8 {
+                let file = BufReader::with_capacity(size, text);
+                assert_eq!(count(file).unwrap(), expected);
+
+                let file = BufReader::with_capacity(size, text);
+                let info =
+                    count_with(file, Counter::new(false, false)).unwrap();
+                assert_eq!(info.num_lines, expected.num_lines);
+                assert_eq!(info.num_bytes, expected.num_bytes);
+            }
+        }
+
+        let info = count(texts[0]).unwrap();
+        assert_eq!((info.num_lines, info.num_words), (3, 6));
+        assert_eq!(info.num_max_line_len, 13);
+    }
+
+    struct Broken;
+
+    impl Read for Broken {
+        fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::other("broken"))
+        }
+    }
+
+    #[test]
+    fn test_count_read_error() {
+        let res = count(BufReader::new(Broken));
+        assert_eq!(res.unwrap_err().to_string(), "broken");
+    }
+
+    #[test]
+    fn test_format_counts() {
+        let info = FileInfo {
+            num_lines: 1,
+            num_words: 10,
+            num_bytes: 48,
+            num_chars: 48,
+            num_max_line_len: 46,
+        };
+        let args = ["wcr", "-lw"].iter().map(OsString::from);
+        let config = get_args_from(args).unwrap();
+        assert_eq!(format_counts(&info, &config, 1), "1 10");
+        assert_eq!(format_counts(&info, &config, 3), "  1  10");
+
+        let config = get_args_from([OsString::from("wcr")]).unwrap();
+        assert_eq!(format_counts(&info, &config, 2), " 1 10 48");
+    }
+
     #[test]
-    fn test_format_field() {
-        assert_eq!(format_field(1, false), "");
-        assert_eq!(format_field(3, true), "       3");
-        assert_eq!(format_field(10, true), "      10");
+    fn test_run_with() {
+        let args = ["wcr", "-lw", "-", "no-such-file"];
+        let config = get_args_from(args.iter().map(OsString::from)).unwrap();
+        let stdin = Cursor::new("one two\nthree\n");
+        let (mut stdout, mut stderr) = (vec![], vec![]);
+        let res = run_with(config, stdin, &mut stdout, &mut stderr);
+        assert_eq!(res.unwrap_err().exit_code(), 1);
+        assert_eq!(String::from_utf8(stdout).unwrap(), "2 3\n2 3 total\n");
+        assert!(stderr.starts_with(b"wcr: no-such-file: "));
     }
 }
@@ -1,4 +1,6 @@
 use assert_cmd::Command;
+#[cfg(target_os = "linux")]
+use clr_io::test_support::{unreadable_error, UNREADABLE};
 use predicates::prelude::*;
 use rand::{distributions::Alphanumeric, Rng};
 use std::fs;
@@ -9,6 +11,7 @@ const PRG: &str = "wcr";
 const EMPTY: &str = "tests/inputs/empty.txt";
 const FOX: &str = "tests/inputs/fox.txt";
 const ATLAMAL: &str = "tests/inputs/atlamal.txt";
+const WIDE: &str = "tests/inputs/wide.txt";
 
 // --------------------------------------------------
 fn gen_bad_file() -> String {
@@ -29,11 +32,11 @@ fn gen_bad_file() -> String {
 #[test]
 fn dies_chars_and_bytes() -> TestResult {
     Command::cargo_bin(PRG)?
-        .args(&["-m", "-c"])
+        .args(["-m", "-c"])
         .assert()
         .failure()
         .stderr(predicate::str::contains(
-            "The argument '--bytes' cannot be used with '--chars'",
+            "The argument '--chars' cannot be used with '--bytes'",
         ));
     Ok(())
 }
@@ -57,7 +60,7 @@ fn skips_bad_file() -> TestResult {
     Command::cargo_bin(PRG)?
         .arg(bad)
         .assert()
-        .success()
+        .failure()
         .stderr(predicate::str::is_match(expected)?);
     Ok(())
 }
@@ -212,3 +215,272 @@ fn test_all_words_lines() -> TestResult {
 fn test_all_bytes_lines() -> TestResult {
     run(&["-cl", EMPTY, FOX, ATLAMAL], "tests/expected/all.cl.out")
 }
+
+// --------------------------------------------------
+#[test]
+fn fox_max_line_length() -> TestResult {
+    run(&["-L", FOX], "tests/expected/fox.txt.L.out")
+}
+
+#[test]
+fn fox_lines_max_line_length() -> TestResult {
+    run(
+        &["--lines", "--max-line-length", FOX],
+        "tests/expected/fox.txt.lL.out",
+    )
+}
+
+#[test]
+fn empty_max_line_length() -> TestResult {
+    run(&["-L", EMPTY], "tests/expected/empty.txt.L.out")
+}
+
+#[test]
+fn atlamal_max_line_length() -> TestResult {
+    run(&["-L", ATLAMAL], "tests/expected/atlamal.txt.L.out")
+}
+
+#[test]
+fn atlamal_lines_max_line_length() -> TestResult {
+    run(&["-lL", ATLAMAL], "tests/expected/atlamal.txt.lL.out")
+}
+
+#[test]
+fn wide_max_line_length() -> TestResult {
+    run(&["-L", WIDE], "tests/expected/wide.txt.L.out")
+}
+
+#[test]
+fn test_all_max_line_length() -> TestResult {
+    run(&["-L", EMPTY, FOX, ATLAMAL], "tests/expected/all.L.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn invalid_utf8() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .arg("-lwmL")
+        .write_stdin(&b"caf\xc3\xa9 \xff\xfe ok\n\xe2\x82"[..])
+        .assert()
+        .success()
+        .stdout(" 1  4 12 10\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(target_os = "linux")]
+fn reports_read_error() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args([FOX, UNREADABLE])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(FOX))
+        .stdout(predicate::str::contains("48 total"))
+        .stderr(predicate::str::starts_with(unreadable_error(PRG)));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn keeps_file_order() -> TestResult {
+    // Named files are counted concurrently, stdin in its place
+    Command::cargo_bin(PRG)?
+        .args(["-l", FOX, "-", ATLAMAL, EMPTY, FOX])
+        .write_stdin("a\nb\nc\n")
+        .assert()
+        .success()
+        .stdout(
+            "1 tests/inputs/fox.txt\n3\n4 tests/inputs/atlamal.txt\n\
+             0 tests/inputs/empty.txt\n1 tests/inputs/fox.txt\n9 total\n",
+        );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn files0_from() -> TestResult {
+    run(
+        &["--files0-from", "tests/inputs/files0.txt"],
+        "tests/expected/all.out",
+    )
+}
+
+#[test]
+fn files0_from_stdin() -> TestResult {
+    let expected = fs::read_to_string("tests/expected/all.l.out")?;
+    Command::cargo_bin(PRG)?
+        .args(["-l", "--files0-from=-"])
+        .write_stdin(fs::read("tests/inputs/files0.txt")?)
+        .assert()
+        .success()
+        .stdout(expected);
+    Ok(())
+}
+
+#[test]
+fn files0_from_bad_names() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-l", "--files0-from=-"])
+        .write_stdin("tests/inputs/fox.txt\0\0-\0tests/inputs/fox.txt")
+        .assert()
+        .failure()
+        .stderr(
+            "wcr: -:2: invalid zero-length file name\n\
+             wcr: when reading file names from standard input, \
+             no file name of '-' allowed\n",
+        )
+        .stdout("1 tests/inputs/fox.txt\n1 tests/inputs/fox.txt\n2 total\n");
+    Ok(())
+}
+
+#[test]
+fn dies_files0_from_missing() -> TestResult {
+    let bad = gen_bad_file();
+    Command::cargo_bin(PRG)?
+        .args(["--files0-from", &bad])
+        .assert()
+        .failure()
+        .stderr(predicate::str::starts_with(format!("wcr: {}: ", bad)));
+    Ok(())
+}
+
+#[test]
+fn dies_files0_from_and_files() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--files0-from", "tests/inputs/files0.txt", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn wide_columns() -> TestResult {
+    // Columns widen to fit the largest count
+    let text = "x\n".repeat(100_000);
+    Command::cargo_bin(PRG)?
+        .args(["-", FOX])
+        .write_stdin(text)
+        .assert()
+        .success()
+        .stdout(
+            "100000 100000 200000\n     1      9     48 tests/inputs/fox.txt\n\
+             100001 100009 200048 total\n",
+        );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn total_always() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--total=always", FOX])
+        .assert()
+        .success()
+        .stdout(" 1  9 48 tests/inputs/fox.txt\n 1  9 48 total\n");
+    Ok(())
+}
+
+#[test]
+fn total_only() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--total", "only", FOX, ATLAMAL])
+        .assert()
+        .success()
+        .stdout("  5  38 225\n");
+
+    Command::cargo_bin(PRG)?
+        .args(["-l", "--total=only", FOX, ATLAMAL])
+        .assert()
+        .success()
+        .stdout("5\n");
+    Ok(())
+}
+
+#[test]
+fn total_never() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-l", "--total=never", FOX, ATLAMAL])
+        .assert()
+        .success()
+        .stdout("1 tests/inputs/fox.txt\n4 tests/inputs/atlamal.txt\n");
+    Ok(())
+}
+
+#[test]
+fn dies_bad_total() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--total=sometimes", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "\"sometimes\" isn't a valid value for '--total <WHEN>'",
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn format_json() -> TestResult {
+    // Every count is given, whatever was selected
+    Command::cargo_bin(PRG)?
+        .args(["-l", "--format", "json", FOX, "-"])
+        .write_stdin("a b\n")
+        .assert()
+        .success()
+        .stdout(concat!(
+            r#"{"file":"tests/inputs/fox.txt","lines":1,"words":9,"#,
+            r#""bytes":48,"chars":48,"max_line_length":50}"#,
+            "\n",
+            r#"{"file":"-","lines":1,"words":2,"bytes":4,"chars":4,"#,
+            r#""max_line_length":3}"#,
+            "\n",
+            r#"{"file":null,"lines":2,"words":11,"bytes":52,"chars":52,"#,
+            r#""max_line_length":50}"#,
+            "\n",
+        ));
+    Ok(())
+}
+
+#[test]
+fn format_csv() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--format=csv", EMPTY, FOX, ATLAMAL])
+        .assert()
+        .success()
+        .stdout(
+            "file,lines,words,bytes,chars,max_line_length\n\
+             tests/inputs/empty.txt,0,0,0,0,0\n\
+             tests/inputs/fox.txt,1,9,48,48,50\n\
+             tests/inputs/atlamal.txt,4,29,177,159,43\n\
+             ,5,38,225,207,50\n",
+        );
+    Ok(())
+}
+
+#[test]
+fn format_tsv() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--format=tsv", "--total=never", FOX])
+        .assert()
+        .success()
+        .stdout(
+            "file\tlines\twords\tbytes\tchars\tmax_line_length\n\
+             tests/inputs/fox.txt\t1\t9\t48\t48\t50\n",
+        );
+    Ok(())
+}
+
+#[test]
+fn dies_bad_format() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--format=xml", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "\"xml\" isn't a valid value for '--format <FORMAT>'",
+        ));
+    Ok(())
+}
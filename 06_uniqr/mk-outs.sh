@@ -12,6 +12,7 @@ echo -ne "a\nb"      > $ROOT/t3.txt
 echo -ne "a\na\nb"   > $ROOT/t4.txt
 echo -ne "b\na\na\n" > $ROOT/t5.txt
 echo -ne "a\nb\nc\n" > $ROOT/t6.txt
+echo -ne "a\nA\nb\nB\nb\nc\n" > $ROOT/case.txt
 
 for FILE in $ROOT/*.txt; do
     BASENAME=$(basename "$FILE")
@@ -20,3 +21,85 @@ for FILE in $ROOT/*.txt; do
     uniq    < $FILE > ${OUT_DIR}/${BASENAME}.stdin.out
     uniq -c < $FILE > ${OUT_DIR}/${BASENAME}.stdin.c.out
 done
+
+# GNU uniq pads counts to seven columns where uniqr, like BSD, uses four
+count() { uniq -c "$@" | sed -E 's/^ {3}//'; }
+
+for FILE in $ROOT/three.txt $ROOT/case.txt; do
+    BASENAME=$(basename "$FILE")
+    uniq -d                    $FILE > ${OUT_DIR}/${BASENAME}.d.out
+    count -d                   $FILE > ${OUT_DIR}/${BASENAME}.c.d.out
+    uniq -u                    $FILE > ${OUT_DIR}/${BASENAME}.u.out
+    count -u                   $FILE > ${OUT_DIR}/${BASENAME}.c.u.out
+    uniq -D                    $FILE > ${OUT_DIR}/${BASENAME}.D.out
+    uniq -D -u                 $FILE > ${OUT_DIR}/${BASENAME}.D.u.out
+    uniq --all-repeated=prepend  $FILE > ${OUT_DIR}/${BASENAME}.D.prepend.out
+    uniq --all-repeated=separate $FILE > ${OUT_DIR}/${BASENAME}.D.separate.out
+    for METHOD in separate prepend append both; do
+        uniq --group=$METHOD $FILE > ${OUT_DIR}/${BASENAME}.group.$METHOD.out
+    done
+done
+
+uniq -i    $ROOT/case.txt > ${OUT_DIR}/case.txt.i.out
+count -i   $ROOT/case.txt > ${OUT_DIR}/case.txt.c.i.out
+uniq -i -d $ROOT/case.txt > ${OUT_DIR}/case.txt.i.d.out
+uniq -i -D $ROOT/case.txt > ${OUT_DIR}/case.txt.i.D.out
+uniq -i --group $ROOT/case.txt > ${OUT_DIR}/case.txt.i.group.out
+
+# Timestamped lines for skipping fields and characters
+printf '%s\n' "10:01 INFO started" "10:02 INFO started" "10:02 INFO Started" \
+    "10:03 WARN disk low" "10:04  WARN disk low" "11:00 INFO started" \
+    > $ROOT/log.txt
+uniq          $ROOT/log.txt > ${OUT_DIR}/log.txt.out
+count         $ROOT/log.txt > ${OUT_DIR}/log.txt.c.out
+uniq        < $ROOT/log.txt > ${OUT_DIR}/log.txt.stdin.out
+count       < $ROOT/log.txt > ${OUT_DIR}/log.txt.stdin.c.out
+count -f 1    $ROOT/log.txt > ${OUT_DIR}/log.txt.c.f1.out
+count -f 1 -i $ROOT/log.txt > ${OUT_DIR}/log.txt.c.f1.i.out
+uniq -s 6     $ROOT/log.txt > ${OUT_DIR}/log.txt.s6.out
+uniq -f 1 -w 5 $ROOT/log.txt > ${OUT_DIR}/log.txt.f1.w5.out
+uniq -w 2 -D  $ROOT/log.txt > ${OUT_DIR}/log.txt.w2.D.out
+uniq -f 1 -s 2 -w 2 --group $ROOT/log.txt \
+    > ${OUT_DIR}/log.txt.f1.s2.w2.group.out
+
+# uniq has no --global, so awk keeps the first of each line in input order
+COUNTS='{ if (!n[$0]++) first[++k] = $0 }
+    END { for (i = 1; i <= k; i++) printf "%4d %s\n", n[first[i]], first[i] }'
+for FILE in $ROOT/three.txt $ROOT/case.txt $ROOT/log.txt; do
+    BASENAME=$(basename "$FILE")
+    awk '!seen[$0]++' $FILE > ${OUT_DIR}/${BASENAME}.global.out
+    awk "$COUNTS"     $FILE > ${OUT_DIR}/${BASENAME}.global.c.out
+done
+awk '!seen[tolower($0)]++' $ROOT/case.txt > ${OUT_DIR}/case.txt.global.i.out
+awk '!seen[substr($0, index($0, " "))]++' $ROOT/log.txt \
+    > ${OUT_DIR}/log.txt.global.f1.out
+awk "$COUNTS" $ROOT/case.txt | awk '$1 > 1' \
+    > ${OUT_DIR}/case.txt.global.c.d.out
+awk "$COUNTS" $ROOT/case.txt | awk '$1 == 1' \
+    > ${OUT_DIR}/case.txt.global.c.u.out
+
+# Trailing blanks count and lines need not be UTF-8; -z reads NUL records
+echo -ne "foo  \nfoo\nfoo\n\xe9t\xe9\n\xe9t\xe9\n\xc9T\xc9\n" > $ROOT/bytes.txt
+echo -ne "a\0a\0b\nc\0b\nc\0B\nC\0"                           > $ROOT/zero.bin
+uniq          $ROOT/bytes.txt > ${OUT_DIR}/bytes.txt.out
+count         $ROOT/bytes.txt > ${OUT_DIR}/bytes.txt.c.out
+uniq        < $ROOT/bytes.txt > ${OUT_DIR}/bytes.txt.stdin.out
+count       < $ROOT/bytes.txt > ${OUT_DIR}/bytes.txt.stdin.c.out
+count -i      $ROOT/bytes.txt > ${OUT_DIR}/bytes.txt.c.i.out
+uniq -z       $ROOT/zero.bin  > ${OUT_DIR}/zero.bin.z.out
+uniq -z -c    $ROOT/zero.bin | sed -E 's/(^|\x0) {3}/\1/g' \
+    > ${OUT_DIR}/zero.bin.z.c.out
+uniq -z -i --group=append $ROOT/zero.bin > ${OUT_DIR}/zero.bin.z.i.group.out
+
+# uniq always keeps the first line of a group, so awk finds the last
+awk '{ k = tolower($0) } NR > 1 && k != p { print l } { p = k; l = $0 }
+    END { if (NR) print l }' $ROOT/case.txt > ${OUT_DIR}/case.txt.i.last.out
+
+# Counts in other formats and the largest groups with their share of lines
+count $ROOT/three.txt | sed -E 's/^ +//; s/ /:/' > ${OUT_DIR}/three.txt.cfmt.out
+TOTAL=$(wc -l < $ROOT/three.txt)
+SHARE='{ printf "%4d %6.2f%% %s\n", $1, 100 * $1 / total, substr($0, 6) }'
+count $ROOT/three.txt | sort -s -k1,1nr | head -n 3 \
+    | awk -v total=$TOTAL "$SHARE" > ${OUT_DIR}/three.txt.top3.out
+awk "$COUNTS" $ROOT/three.txt | sort -s -k1,1nr | head -n 2 \
+    | awk -v total=$TOTAL "$SHARE" > ${OUT_DIR}/three.txt.global.top2.out
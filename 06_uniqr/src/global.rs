@@ -0,0 +1,188 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Seek, Write},
+};
+use xxhash_rust::xxh3::xxh3_64;
+
+// A rough allowance for the bookkeeping of each remembered line
+const OVERHEAD: u64 = 64;
+
+// The groups seen so far by --global, in order of first appearance.
+// Keys are kept whole until they use more than the memory limit, after
+// which only their 64-bit hashes are kept and the lines to report are
+// spilled to a temporary file.
+pub struct Global {
+    exact: HashMap<Vec<u8>, usize>,
+    hashed: HashMap<u64, usize>,
+    counts: Vec<u64>,
+    lines: Vec<Vec<u8>>,
+    spill: Option<BufWriter<File>>,
+    keep_lines: bool,
+    used: u64,
+    max_memory: Option<u64>,
+    separator: u8,
+}
+
+impl Global {
+    // `keep_lines` says whether `finish` will need the first line of
+    // each group, rather than the caller printing them as they arrive
+    pub fn new(
+        max_memory: Option<u64>,
+        keep_lines: bool,
+        separator: u8,
+    ) -> Self {
+        Global {
+            exact: HashMap::new(),
+            hashed: HashMap::new(),
+            counts: vec![],
+            lines: vec![],
+            spill: None,
+            keep_lines,
+            used: 0,
+            max_memory,
+            separator,
+        }
+    }
+
+    // Whether the limit was reached and keys are now compared by hash
+    pub fn is_hashed(&self) -> bool {
+        self.used > self.max_memory.unwrap_or(u64::MAX)
+    }
+
+    // Count a line under its key, returning whether it starts a group
+    pub fn add(&mut self, key: &[u8], line: &[u8]) -> io::Result<bool> {
+        let next = self.counts.len();
+        let found = if self.is_hashed() {
+            self.hashed.get(&xxh3_64(key)).copied()
+        } else {
+            self.exact.get(key).copied()
+        };
+        if let Some(i) = found {
+            self.counts[i] += 1;
+            return Ok(false);
+        }
+
+        self.counts.push(1);
+        if self.is_hashed() {
+            self.hashed.insert(xxh3_64(key), next);
+            if let Some(spill) = &mut self.spill {
+                spill.write_all(line)?;
+            }
+            return Ok(true);
+        }
+
+        self.exact.insert(key.to_vec(), next);
+        self.used += key.len() as u64 + OVERHEAD;
+        if self.keep_lines {
+            self.lines.push(line.to_vec());
+            self.used += line.len() as u64;
+        }
+        if self.is_hashed() {
+            self.start_hashing()?;
+        }
+        Ok(true)
+    }
+
+    // Trade the keys for their hashes and the lines for a file
+    fn start_hashing(&mut self) -> io::Result<()> {
+        self.hashed = self
+            .exact
+            .drain()
+            .map(|(key, i)| (xxh3_64(&key), i))
+            .collect();
+        self.exact = HashMap::new();
+        if self.keep_lines {
+            let mut spill = BufWriter::new(tempfile::tempfile()?);
+            for line in self.lines.drain(..) {
+                spill.write_all(&line)?;
+            }
+            self.lines = vec![];
+            self.spill = Some(spill);
+        }
+        Ok(())
+    }
+
+    // Call `f` with the count and first line of each group in order
+    pub fn finish<E: From<io::Error>>(
+        self,
+        mut f: impl FnMut(u64, &[u8]) -> Result<(), E>,
+    ) -> Result<(), E> {
+        match self.spill {
+            Some(spill) => {
+                let mut file =
+                    spill.into_inner().map_err(|e| e.into_error())?;
+                file.rewind()?;
+                let mut file = BufReader::new(file);
+                let mut line = vec![];
+                for count in self.counts {
+                    line.clear();
+                    file.read_until(self.separator, &mut line)?;
+                    f(count, &line)?;
+                }
+            }
+            None => {
+                for (count, line) in self.counts.into_iter().zip(&self.lines) {
+                    f(count, line)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// --------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::Global;
+    use std::io;
+
+    fn groups(seen: Global) -> Vec<(u64, Vec<u8>)> {
+        let mut groups = vec![];
+        seen.finish(|count, line| {
+            groups.push((count, line.to_vec()));
+            Ok::<_, io::Error>(())
+        })
+        .unwrap();
+        groups
+    }
+
+    #[test]
+    fn test_add() {
+        let mut seen = Global::new(None, true, b'\n');
+        assert!(seen.add(b"a", b"a\n").unwrap());
+        assert!(seen.add(b"b", b"b\n").unwrap());
+        assert!(!seen.add(b"a", b"A\n").unwrap());
+        assert!(!seen.is_hashed());
+        assert_eq!(groups(seen), [(2, b"a\n".to_vec()), (1, b"b\n".to_vec())]);
+    }
+
+    #[test]
+    fn test_spill() {
+        // The limit is passed by the second group
+        let mut seen = Global::new(Some(100), true, b'\0');
+        for key in ["a", "b", "a", "c", "b", "d", "a"] {
+            seen.add(key.as_bytes(), format!("{}\n\0", key).as_bytes())
+                .unwrap();
+            assert_eq!(seen.is_hashed(), key != "a" || seen.counts.len() > 1);
+        }
+        assert!(seen.exact.is_empty() && seen.lines.is_empty());
+        let counts: Vec<_> = groups(seen)
+            .into_iter()
+            .map(|(count, line)| {
+                format!("{} {}", count, String::from_utf8(line).unwrap())
+            })
+            .collect();
+        assert_eq!(counts, ["3 a\n\0", "2 b\n\0", "1 c\n\0", "1 d\n\0"]);
+    }
+
+    #[test]
+    fn test_hashed_without_lines() {
+        let mut seen = Global::new(Some(0), false, b'\n');
+        assert!(seen.add(b"a", b"a\n").unwrap());
+        assert!(seen.is_hashed() && seen.spill.is_none());
+        assert!(!seen.add(b"a", b"a\n").unwrap());
+        assert!(seen.add(b"b", b"b\n").unwrap());
+        assert!(groups(seen).is_empty());
+    }
+}
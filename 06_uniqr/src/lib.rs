@@ -1,21 +1,85 @@
+mod global;
+
 use clap::{Command, Arg};
+use clr_io::{size::parse_size, Error};
+use global::Global;
+use serde_json::json;
 use std::{
-    error::Error,
+    cmp::Reverse,
+    env,
+    ffi::OsString,
     fs::File,
-    io::{self, BufRead, BufReader, Write},
+    io::{self, BufRead, Write},
+    str,
 };
 
-type MyResult<T> = Result<T, Box<dyn Error>>;
+type MyResult<T> = clr_io::Result<T>;
+
+const PRG: &str = "uniqr";
+
+// Wider --count-format fields are refused rather than padded
+const MAX_WIDTH: usize = 1024;
 
 #[derive(Debug)]
 pub struct Config {
     in_file: String,
     out_file: Option<String>,
     count: bool,
+    repeated: bool,
+    unique: bool,
+    all_repeated: bool,
+    group: bool,
+    delimit: Delimit,
+    ignore_case: bool,
+    skip_fields: usize,
+    skip_chars: usize,
+    check_chars: Option<usize>,
+    global: bool,
+    max_memory: Option<u64>,
+    separator: u8,
+    keep_last: bool,
+    count_format: CountFormat,
+    top: Option<usize>,
+    format: Format,
+}
+
+// A printf-style format for the counts, such as "%7d " or "%-4d| "
+#[derive(Debug, PartialEq, Eq)]
+pub struct CountFormat {
+    before: String,
+    after: String,
+    width: usize,
+    left: bool,
+    zero: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    Json,
+    Tsv,
+}
+
+// Where -D and --group put empty lines around the groups they print
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delimit {
+    None,
+    Prepend,
+    Append,
+    Separate,
+    Both,
 }
 
 // --------------------------------------------------
 pub fn get_args() -> MyResult<Config> {
+    get_args_from(env::args_os())
+}
+
+// --------------------------------------------------
+pub fn get_args_from<I>(args: I) -> MyResult<Config>
+where
+    I: IntoIterator<Item = OsString>,
+{
     let matches = Command::new("uniqr")
         .version("0.1.0")
         .author("Ken Youens-Clark <kyclark@gmail.com>")
@@ -39,7 +103,138 @@ pub fn get_args() -> MyResult<Config> {
                 .help("Show counts")
                 .takes_value(false),
         )
-        .get_matches();
+        .arg(
+            Arg::new("repeated")
+                .short('d')
+                .long("repeated")
+                .help("Only print duplicate lines, one for each group")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::new("all_repeated")
+                .short('D')
+                .long("all-repeated")
+                .value_name("METHOD")
+                .help("Print all duplicate lines, delimiting groups")
+                .min_values(0)
+                .max_values(1)
+                .require_equals(true)
+                .default_missing_value("none")
+                .possible_values(["none", "prepend", "separate"])
+                .conflicts_with("count"),
+        )
+        .arg(
+            Arg::new("unique")
+                .short('u')
+                .long("unique")
+                .help("Only print unique lines")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::new("group")
+                .long("group")
+                .value_name("METHOD")
+                .help("Print all lines, delimiting groups")
+                .min_values(0)
+                .max_values(1)
+                .require_equals(true)
+                .default_missing_value("separate")
+                .possible_values(["separate", "prepend", "append", "both"])
+                .conflicts_with_all(&[
+                    "count",
+                    "repeated",
+                    "all_repeated",
+                    "unique",
+                ]),
+        )
+        .arg(
+            Arg::new("ignore_case")
+                .short('i')
+                .long("ignore-case")
+                .help("Ignore differences in case when comparing")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::new("skip_fields")
+                .short('f')
+                .long("skip-fields")
+                .value_name("N")
+                .help("Skip the first N fields when comparing")
+                .allow_hyphen_values(true),
+        )
+        .arg(
+            Arg::new("skip_chars")
+                .short('s')
+                .long("skip-chars")
+                .value_name("N")
+                .help("Skip the first N characters when comparing")
+                .allow_hyphen_values(true),
+        )
+        .arg(
+            Arg::new("check_chars")
+                .short('w')
+                .long("check-chars")
+                .value_name("N")
+                .help("Compare no more than N characters")
+                .allow_hyphen_values(true),
+        )
+        .arg(
+            Arg::new("global")
+                .long("global")
+                .help("Merge all matching lines, not just adjacent ones")
+                .takes_value(false)
+                .conflicts_with_all(&["all_repeated", "group"]),
+        )
+        .arg(
+            Arg::new("max_memory")
+                .long("max-memory")
+                .value_name("SIZE")
+                .help(
+                    "Compare lines by hash once --global remembers this much \
+                    (suffixes K, M, G, ...)",
+                )
+                .requires("global"),
+        )
+        .arg(
+            Arg::new("zero_terminated")
+                .short('z')
+                .long("zero-terminated")
+                .help("Line delimiter is NUL, not newline")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::new("keep")
+                .long("keep")
+                .value_name("WHICH")
+                .help("Print the first or the last line of each group")
+                .possible_values(["first", "last"])
+                .default_value("first")
+                .conflicts_with_all(&["all_repeated", "group", "global"]),
+        )
+        .arg(
+            Arg::new("count_format")
+                .long("count-format")
+                .value_name("FMT")
+                .help("Print counts with this format, e.g. '%7d ' [implies -c]")
+                .conflicts_with_all(&["all_repeated", "group"]),
+        )
+        .arg(
+            Arg::new("top")
+                .long("top")
+                .value_name("N")
+                .help("Report the N largest groups with their share of lines")
+                .conflicts_with_all(&["all_repeated", "group"]),
+        )
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .value_name("FORMAT")
+                .help("Output format of --top")
+                .possible_values(["text", "json", "tsv"])
+                .default_value("text")
+                .requires("top"),
+        )
+        .try_get_matches_from(args)?;
 
     Ok(Config {
         //in_file: matches.value_of_lossy("in_file").unwrap().to_string(),
@@ -47,58 +242,560 @@ pub fn get_args() -> MyResult<Config> {
         //in_file: matches.value_of_lossy("in_file").map(|v| v.into()).unwrap(),
         in_file: matches.value_of_lossy("in_file").map(Into::into).unwrap(),
         out_file: matches.value_of("out_file").map(|v| v.to_string()),
-        count: matches.is_present("count"),
+        count: matches.is_present("count")
+            || matches.is_present("count_format"),
+        repeated: matches.is_present("repeated"),
+        unique: matches.is_present("unique"),
+        all_repeated: matches.is_present("all_repeated"),
+        group: matches.is_present("group"),
+        delimit: matches
+            .value_of("all_repeated")
+            .or_else(|| matches.value_of("group"))
+            .map_or(Delimit::None, Delimit::from_name),
+        ignore_case: matches.is_present("ignore_case"),
+        skip_fields: matches
+            .value_of("skip_fields")
+            .map(|v| parse_num(v, "fields to skip"))
+            .transpose()?
+            .unwrap_or(0),
+        skip_chars: matches
+            .value_of("skip_chars")
+            .map(|v| parse_num(v, "characters to skip"))
+            .transpose()?
+            .unwrap_or(0),
+        check_chars: matches
+            .value_of("check_chars")
+            .map(|v| parse_num(v, "characters to compare"))
+            .transpose()?,
+        global: matches.is_present("global"),
+        max_memory: matches
+            .value_of("max_memory")
+            .map(|v| parse_size(v).map_err(|e| format!("{}: {}", v, e)))
+            .transpose()?,
+        separator: if matches.is_present("zero_terminated") {
+            b'\0'
+        } else {
+            b'\n'
+        },
+        keep_last: matches.value_of("keep") == Some("last"),
+        count_format: parse_count_format(
+            matches.value_of("count_format").unwrap_or("%4d "),
+        )?,
+        top: matches
+            .value_of("top")
+            .map(|v| parse_num(v, "groups to report"))
+            .transpose()?,
+        format: match matches.value_of("format") {
+            Some("json") => Format::Json,
+            Some("tsv") => Format::Tsv,
+            _ => Format::Text,
+        },
+    })
+}
+
+// --------------------------------------------------
+fn parse_count_format(val: &str) -> MyResult<CountFormat> {
+    let err = || Error::Msg(format!("invalid count format -- {}", val));
+    let mut text = String::new();
+    let mut before = None;
+    let (mut width, mut left, mut zero) = (0, false, false);
+    let mut chars = val.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            text.push(c);
+        } else if chars.next_if_eq(&'%').is_some() {
+            text.push('%');
+        } else if before.is_some() {
+            return Err(err());
+        } else {
+            left = chars.next_if_eq(&'-').is_some();
+            zero = chars.next_if_eq(&'0').is_some();
+            let mut digits = String::new();
+            while let Some(d) = chars.next_if(char::is_ascii_digit) {
+                digits.push(d);
+            }
+            if !digits.is_empty() {
+                width = digits
+                    .parse()
+                    .ok()
+                    .filter(|&width| width <= MAX_WIDTH)
+                    .ok_or_else(err)?;
+            }
+            if chars.next() != Some('d') {
+                return Err(err());
+            }
+            before = Some(std::mem::take(&mut text));
+        }
+    }
+
+    Ok(CountFormat {
+        before: before.ok_or_else(err)?,
+        after: text,
+        width,
+        left,
+        zero,
     })
 }
 
+// --------------------------------------------------
+fn parse_num(val: &str, what: &str) -> MyResult<usize> {
+    val.parse()
+        .map_err(|_| Error::Msg(format!("{}: invalid number of {}", val, what)))
+}
+
+// --------------------------------------------------
+impl Delimit {
+    // The names have already been checked by clap
+    fn from_name(name: &str) -> Self {
+        match name {
+            "prepend" => Delimit::Prepend,
+            "append" => Delimit::Append,
+            "separate" => Delimit::Separate,
+            "both" => Delimit::Both,
+            _ => Delimit::None,
+        }
+    }
+
+    // Whether an empty line goes before a group, given how many came before
+    fn before(self, groups: u64) -> bool {
+        match self {
+            Delimit::None => false,
+            Delimit::Prepend | Delimit::Both => true,
+            Delimit::Append | Delimit::Separate => groups > 0,
+        }
+    }
+
+    // Whether an empty line follows the last group
+    fn after(self, groups: u64) -> bool {
+        matches!(self, Delimit::Append | Delimit::Both) && groups > 0
+    }
+}
+
+// --------------------------------------------------
+impl CountFormat {
+    fn format(&self, count: u64) -> String {
+        let (width, before, after) = (self.width, &self.before, &self.after);
+        match (self.left, self.zero) {
+            (true, _) => format!("{}{:<width$}{}", before, count, after),
+            (false, true) => format!("{}{:0width$}{}", before, count, after),
+            (false, false) => format!("{}{:width$}{}", before, count, after),
+        }
+    }
+}
+
 // --------------------------------------------------
 pub fn run(config: Config) -> MyResult<()> {
-    let mut file = open(&config.in_file)
-        .map_err(|e| format!("{}: {}", config.in_file, e))?;
+    run_with(
+        config,
+        io::stdin().lock(),
+        io::stdout().lock(),
+        io::stderr(),
+    )
+}
+
+// --------------------------------------------------
+pub fn run_with(
+    config: Config,
+    stdin: impl BufRead,
+    stdout: impl Write,
+    mut stderr: impl Write,
+) -> MyResult<()> {
+    let mut file = clr_io::open_with(&config.in_file, stdin)?;
 
     let mut out_file: Box<dyn Write> = match &config.out_file {
-        Some(out_name) => Box::new(File::create(out_name)?),
-        _ => Box::new(io::stdout()),
+        Some(out_name) => Box::new(
+            File::create(out_name).map_err(|e| Error::file(out_name, e))?,
+        ),
+        _ => Box::new(stdout),
     };
 
-    let mut print = |count: u64, text: &str| -> MyResult<()> {
-        if count > 0 {
-            if config.count {
-                write!(out_file, "{:>4} {}", count, text)?;
-            } else {
-                write!(out_file, "{}", text)?;
+    // Records only lack a terminator at the end of the input, so add one
+    // if anything else has to follow
+    // With --top, the groups are collected for the report instead
+    let sep = config.separator;
+    let mut line_start = true;
+    let mut top = vec![];
+    let mut print = |count: u64, text: &[u8]| -> MyResult<()> {
+        if let Some(n) = config.top {
+            top.push((count, text.to_vec()));
+            // Keep the list short, relying on a stable sort to rank the
+            // first of equal groups highest
+            if top.len() >= n.max(512).saturating_mul(2) {
+                top.sort_by_key(|(count, _)| Reverse(*count));
+                top.truncate(n);
             }
-        };
+            return Ok(());
+        }
+        if !line_start {
+            out_file.write_all(&[sep])?;
+        }
+        if config.count {
+            write!(out_file, "{}", config.count_format.format(count))?;
+        }
+        out_file.write_all(text)?;
+        line_start = text.last() == Some(&sep);
         Ok(())
     };
 
-    let mut line = String::new();
-    let mut previous = String::new();
+    // Whether a group of this size gets its one line printed
+    let wanted = |count: u64| {
+        !(config.all_repeated
+            || config.group
+            || config.repeated && count < 2
+            || config.unique && count > 1)
+    };
+
+    let mut line = vec![];
+    let mut total: u64 = 0;
+    if config.global {
+        // Only the counts need the whole input before printing
+        let keep_lines = config.count
+            || config.repeated
+            || config.unique
+            || config.top.is_some();
+        let mut seen = Global::new(config.max_memory, keep_lines, sep);
+        let mut hashed = false;
+        loop {
+            line.clear();
+            let bytes = file
+                .read_until(sep, &mut line)
+                .map_err(|e| Error::file(&config.in_file, e))?;
+            if bytes == 0 {
+                break;
+            }
+            total += 1;
+
+            let key = key(&line, &config);
+            let new = if config.ignore_case {
+                seen.add(&fold_case(key), &line)?
+            } else {
+                seen.add(key, &line)?
+            };
+            if new && !keep_lines {
+                print(1, &line)?;
+            }
+            if !hashed && seen.is_hashed() {
+                hashed = true;
+                let msg = "memory limit reached, comparing hashes of lines";
+                clr_io::report_to(&mut stderr, PRG, &Error::from(msg));
+            }
+        }
+        seen.finish(|count, line| {
+            if wanted(count) {
+                print(count, line)?;
+            }
+            Ok::<_, Error>(())
+        })?;
+        return print_report(&mut out_file, top, total, &config);
+    }
+
+    let mut previous = vec![];
     let mut count: u64 = 0;
+    let mut groups: u64 = 0;
     loop {
-        let bytes = file.read_line(&mut line)?;
+        line.clear();
+        let bytes = file
+            .read_until(sep, &mut line)
+            .map_err(|e| Error::file(&config.in_file, e))?;
         if bytes == 0 {
             break;
         }
+        total += 1;
 
-        if line.trim_end() != previous.trim_end() {
+        if count > 0
+            && same(
+                key(&line, &config),
+                key(&previous, &config),
+                config.ignore_case,
+            )
+        {
+            count += 1;
+            if config.all_repeated && count == 2 {
+                if config.delimit.before(groups) {
+                    print(0, &[sep])?;
+                }
+                groups += 1;
+                // With -u, only the later lines of each group are printed
+                if !config.unique {
+                    print(0, &previous)?;
+                }
+            }
+            if config.all_repeated || config.group {
+                print(0, &line)?;
+            } else if config.keep_last {
+                std::mem::swap(&mut previous, &mut line);
+            }
+            continue;
+        }
+
+        if count > 0 && wanted(count) {
             print(count, &previous)?;
-            previous = line.clone();
-            count = 0;
         }
+        std::mem::swap(&mut previous, &mut line);
+        count = 1;
 
-        count += 1;
-        line.clear();
+        if config.group {
+            if config.delimit.before(groups) {
+                print(0, &[sep])?;
+            }
+            groups += 1;
+            print(0, &previous)?;
+        }
+    }
+    if count > 0 && wanted(count) {
+        print(count, &previous)?;
     }
-    print(count, &previous)?;
+    if config.delimit.after(groups) {
+        print(0, &[sep])?;
+    }
+
+    print_report(&mut out_file, top, total, &config)
+}
 
+// --------------------------------------------------
+// Print the --top groups by size with their share of all the lines
+fn print_report(
+    out: &mut impl Write,
+    mut top: Vec<(u64, Vec<u8>)>,
+    total: u64,
+    config: &Config,
+) -> MyResult<()> {
+    let n = match config.top {
+        Some(n) => n,
+        _ => return Ok(()),
+    };
+    top.sort_by_key(|(count, _)| Reverse(*count));
+    top.truncate(n);
+
+    let sep = config.separator;
+    for (count, line) in top {
+        let line = line.strip_suffix(&[sep]).unwrap_or(&line);
+        let percent = (10000.0 * count as f64 / total as f64).round() / 100.0;
+        match config.format {
+            Format::Text => {
+                let count = config.count_format.format(count);
+                write!(out, "{}{:6.2}% ", count, percent)?;
+                out.write_all(line)?;
+                out.write_all(&[sep])?;
+            }
+            Format::Tsv => {
+                write!(out, "{}\t{:.2}\t", count, percent)?;
+                out.write_all(line)?;
+                out.write_all(&[sep])?;
+            }
+            Format::Json => {
+                let line = String::from_utf8_lossy(line);
+                let group = json!({
+                    "count": count,
+                    "percent": percent,
+                    "line": line,
+                });
+                writeln!(out, "{}", group)?;
+            }
+        }
+    }
     Ok(())
 }
 
 // --------------------------------------------------
-fn open(filename: &str) -> MyResult<Box<dyn BufRead>> {
-    match filename {
-        "-" => Ok(Box::new(BufReader::new(io::stdin()))),
-        _ => Ok(Box::new(BufReader::new(File::open(filename)?))),
+// The part of a record that is compared: the terminator is dropped, -f
+// fields and then -s characters are skipped, and no more than -w
+// characters are kept
+fn key<'a>(line: &'a [u8], config: &Config) -> &'a [u8] {
+    let is_blank = |b: &u8| *b == b' ' || *b == b'\t';
+    let mut key = line.strip_suffix(&[config.separator]).unwrap_or(line);
+    for _ in 0..config.skip_fields {
+        if key.is_empty() {
+            break;
+        }
+        let start = key.iter().position(|b| !is_blank(b)).unwrap_or(key.len());
+        key = &key[start..];
+        let end = key.iter().position(is_blank).unwrap_or(key.len());
+        key = &key[end..];
+    }
+    key = &key[char_offset(key, config.skip_chars)..];
+    if let Some(n) = config.check_chars {
+        key = &key[..char_offset(key, n)];
+    }
+    key
+}
+
+// --------------------------------------------------
+// Where the nth character starts, taking any byte that does not continue
+// a UTF-8 sequence to start one
+fn char_offset(bytes: &[u8], n: usize) -> usize {
+    bytes
+        .iter()
+        .enumerate()
+        .filter(|(_, &b)| b & 0xC0 != 0x80)
+        .nth(n)
+        .map_or(bytes.len(), |(i, _)| i)
+}
+
+// --------------------------------------------------
+fn same(line: &[u8], previous: &[u8], ignore_case: bool) -> bool {
+    if !ignore_case {
+        return line == previous;
+    }
+    match (str::from_utf8(line), str::from_utf8(previous)) {
+        (Ok(line), Ok(previous)) => line
+            .chars()
+            .flat_map(char::to_lowercase)
+            .eq(previous.chars().flat_map(char::to_lowercase)),
+        _ => line.eq_ignore_ascii_case(previous),
+    }
+}
+
+// --------------------------------------------------
+// The key -i compares, lowercasing only ASCII unless it is valid UTF-8
+fn fold_case(key: &[u8]) -> Vec<u8> {
+    match str::from_utf8(key) {
+        Ok(key) => key.to_lowercase().into_bytes(),
+        _ => key.to_ascii_lowercase(),
+    }
+}
+
+// --------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::{
+        fold_case, get_args_from, key, parse_count_format, run_with, same,
+        Delimit,
+    };
+    use std::{ffi::OsString, io::Cursor};
+
+    #[test]
+    fn test_run_with() {
+        let args = ["uniqr", "-c"];
+        let config = get_args_from(args.iter().map(OsString::from)).unwrap();
+        let stdin = Cursor::new("a\na\nb\n");
+        let mut stdout = vec![];
+        run_with(config, stdin, &mut stdout, vec![]).unwrap();
+        assert_eq!(stdout, b"   2 a\n   1 b\n");
+    }
+
+    #[test]
+    fn test_same() {
+        assert!(same(b"a", b"a", false));
+        assert!(!same(b"a", b"A", false));
+        assert!(!same(b"a ", b"a", false));
+        assert!(same(b"a", b"A", true));
+        assert!(same("Öl".as_bytes(), "öL".as_bytes(), true));
+        assert!(!same(b"a", b"b", true));
+        assert!(same(b"\xffa", b"\xffA", true));
+    }
+
+    #[test]
+    fn test_fold_case() {
+        assert_eq!(fold_case("ÖL".as_bytes()), "öl".as_bytes());
+        assert_eq!(fold_case(b"\xc3\x96L\xff"), b"\xc3\x96l\xff");
+    }
+
+    #[test]
+    fn test_delimit() {
+        assert!(!Delimit::None.before(1));
+        assert!(Delimit::Prepend.before(0));
+        assert!(!Delimit::Separate.before(0));
+        assert!(Delimit::Separate.before(1));
+        assert!(!Delimit::Separate.after(1));
+        assert!(Delimit::Both.after(1));
+        assert!(!Delimit::Append.after(0));
+    }
+
+    #[test]
+    fn test_run_with_group() {
+        let args = ["uniqr", "--group=both", "-i"];
+        let config = get_args_from(args.iter().map(OsString::from)).unwrap();
+        // A missing final newline is added before the closing delimiter
+        let stdin = Cursor::new("a\nA\nb");
+        let mut stdout = vec![];
+        run_with(config, stdin, &mut stdout, vec![]).unwrap();
+        assert_eq!(stdout, b"\na\nA\n\nb\n\n");
+    }
+
+    #[test]
+    fn test_key() {
+        let config = |args: &[&str]| {
+            let args = ["uniqr"].iter().chain(args).map(OsString::from);
+            get_args_from(args).unwrap()
+        };
+        let key = |args: &[&str]| {
+            let line = "10:01 \tcafé  open \n".as_bytes();
+            String::from_utf8(key(line, &config(args)).to_vec()).unwrap()
+        };
+        assert_eq!(key(&[]), "10:01 \tcafé  open ");
+        assert_eq!(key(&["-f", "1"]), " \tcafé  open ");
+        assert_eq!(key(&["-f", "2"]), "  open ");
+        assert_eq!(key(&["-f", "9"]), "");
+        assert_eq!(key(&["-s", "3"]), "01 \tcafé  open ");
+        assert_eq!(key(&["-s", "99"]), "");
+        assert_eq!(key(&["-f1", "-s2"]), "café  open ");
+        assert_eq!(key(&["-f1", "-s2", "-w4"]), "café");
+        assert_eq!(key(&["-w", "0"]), "");
+        assert_eq!(key(&["-w", "99"]), "10:01 \tcafé  open ");
+        assert_eq!(key(&["-z"]), "10:01 \tcafé  open \n");
+    }
+
+    #[test]
+    fn test_bad_num() {
+        let args = ["uniqr", "-f", "x"].iter().map(OsString::from);
+        let err = get_args_from(args).unwrap_err();
+        assert_eq!(err.to_string(), "x: invalid number of fields to skip");
+    }
+
+    #[test]
+    fn test_run_with_bytes() {
+        let args = ["uniqr", "-c"];
+        let config = get_args_from(args.iter().map(OsString::from)).unwrap();
+        let stdin = Cursor::new(b"\xff\n\xff\nfoo  \nfoo\n".to_vec());
+        let mut stdout = vec![];
+        run_with(config, stdin, &mut stdout, vec![]).unwrap();
+        assert_eq!(stdout, b"   2 \xff\n   1 foo  \n   1 foo\n");
+    }
+
+    #[test]
+    fn test_run_with_last() {
+        let args = ["uniqr", "-z", "-i", "--keep", "last"];
+        let config = get_args_from(args.iter().map(OsString::from)).unwrap();
+        let stdin = Cursor::new("a\0A\0b\nc\0B\nC");
+        let mut stdout = vec![];
+        run_with(config, stdin, &mut stdout, vec![]).unwrap();
+        assert_eq!(stdout, b"A\0B\nC");
+    }
+
+    #[test]
+    fn test_count_format() {
+        let format =
+            |fmt, count| parse_count_format(fmt).unwrap().format(count);
+        assert_eq!(format("%4d ", 12), "  12 ");
+        assert_eq!(format("%d", 12), "12");
+        assert_eq!(format("%-4d|", 12), "12  |");
+        assert_eq!(format("%04d", 12), "0012");
+        assert_eq!(format("%-04d", 12), "12  ");
+        assert_eq!(format("[%2d] ", 123), "[123] ");
+        assert_eq!(format("%d%% ", 5), "5% ");
+        assert_eq!(format("%1024d", 1).len(), 1024);
+        for fmt in ["", "%", "%x", "%4", "%%", "%d%d", "%1025d", "%65536d"] {
+            assert!(parse_count_format(fmt).is_err(), "{}", fmt);
+        }
+    }
+
+    #[test]
+    fn test_run_with_top() {
+        let args = ["uniqr", "--top", "1", "--count-format", "%d "];
+        let config = get_args_from(args.iter().map(OsString::from)).unwrap();
+        // The first of equally large groups ranks highest
+        let stdin = Cursor::new("a\nb\nb\nc\nc\n");
+        let mut stdout = vec![];
+        run_with(config, stdin, &mut stdout, vec![]).unwrap();
+        assert_eq!(stdout, b"2  40.00% b\n");
+
+        // An unbounded list doesn't overflow the size it's trimmed at
+        let args = ["uniqr", "--top", "18446744073709551615"];
+        let config = get_args_from(args.iter().map(OsString::from)).unwrap();
+        let stdin = Cursor::new("a\nb\nb\n");
+        let mut stdout = vec![];
+        run_with(config, stdin, &mut stdout, vec![]).unwrap();
+        assert_eq!(stdout, b"   2  66.67% b\n   1  33.33% a\n");
     }
 }
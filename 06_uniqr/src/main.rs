@@ -1,6 +1,5 @@
 fn main() {
     if let Err(e) = uniqr::get_args().and_then(uniqr::run) {
-        eprintln!("{}", e);
-        std::process::exit(1);
+        clr_io::exit("uniqr", e);
     }
 }
@@ -1,4 +1,6 @@
 use assert_cmd::Command;
+#[cfg(target_os = "linux")]
+use clr_io::test_support::{unreadable_error, UNREADABLE};
 use predicates::prelude::*;
 use rand::{distributions::Alphanumeric, Rng};
 use std::fs;
@@ -80,6 +82,24 @@ const T6: Test = Test {
     out_count: "tests/expected/t6.txt.c.out",
 };
 
+const CASE: Test = Test {
+    input: "tests/inputs/case.txt",
+    out: "tests/expected/case.txt.out",
+    out_count: "tests/expected/case.txt.c.out",
+};
+
+const LOG: Test = Test {
+    input: "tests/inputs/log.txt",
+    out: "tests/expected/log.txt.out",
+    out_count: "tests/expected/log.txt.c.out",
+};
+
+const BYTES: Test = Test {
+    input: "tests/inputs/bytes.txt",
+    out: "tests/expected/bytes.txt.out",
+    out_count: "tests/expected/bytes.txt.c.out",
+};
+
 // --------------------------------------------------
 fn gen_bad_file() -> String {
     loop {
@@ -108,10 +128,151 @@ fn dies_bad_file() -> TestResult {
     Ok(())
 }
 
+// --------------------------------------------------
+#[test]
+#[cfg(target_os = "linux")]
+fn dies_unreadable_file() -> TestResult {
+    for args in [&[][..], &["--global"]] {
+        Command::cargo_bin(PRG)?
+            .arg(UNREADABLE)
+            .args(args)
+            .assert()
+            .failure()
+            .stderr(predicate::str::starts_with(unreadable_error(PRG)));
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_all_repeated_count() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args([THREE.input, "-D", "-c"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_group_unique() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args([THREE.input, "--group", "-u"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_group() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args([THREE.input, "--group=foo"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "\"foo\" isn't a valid value for '--group",
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_skip_fields() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args([LOG.input, "-f", "-1"])
+        .assert()
+        .failure()
+        .stderr("uniqr: -1: invalid number of fields to skip\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_check_chars() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args([LOG.input, "--check-chars=x"])
+        .assert()
+        .failure()
+        .stderr("uniqr: x: invalid number of characters to compare\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_max_memory_without_global() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args([THREE.input, "--max-memory", "1M"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--global"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_max_memory() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args([THREE.input, "--global", "--max-memory", "1X"])
+        .assert()
+        .failure()
+        .stderr("uniqr: 1X: invalid number\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_keep_last_global() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args([THREE.input, "--global", "--keep", "last"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_count_format() -> TestResult {
+    for fmt in ["%s", "count", "%d %d"] {
+        Command::cargo_bin(PRG)?
+            .args([THREE.input, "--count-format", fmt])
+            .assert()
+            .failure()
+            .stderr(format!("uniqr: invalid count format -- {}\n", fmt));
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_format_without_top() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args([THREE.input, "--format", "json"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--top <N>"));
+    Ok(())
+}
+
 // --------------------------------------------------
 // HELPER FUNCTIONS
+fn run_args(input: &str, args: &[&str], expected_file: &str) -> TestResult {
+    let expected = fs::read(expected_file)?;
+    Command::cargo_bin(PRG)?
+        .arg(input)
+        .args(args)
+        .assert()
+        .success()
+        .stdout(expected);
+    Ok(())
+}
+
+// --------------------------------------------------
 fn run(test: &Test) -> TestResult {
-    let expected = fs::read_to_string(test.out)?;
+    let expected = fs::read(test.out)?;
     Command::cargo_bin(PRG)?
         .arg(test.input)
         .assert()
@@ -122,9 +283,9 @@ fn run(test: &Test) -> TestResult {
 
 // --------------------------------------------------
 fn run_count(test: &Test) -> TestResult {
-    let expected = fs::read_to_string(test.out_count)?;
+    let expected = fs::read(test.out_count)?;
     Command::cargo_bin(PRG)?
-        .args(&[test.input, "-c"])
+        .args([test.input, "-c"])
         .assert()
         .success()
         .stdout(expected);
@@ -133,8 +294,8 @@ fn run_count(test: &Test) -> TestResult {
 
 // --------------------------------------------------
 fn run_stdin(test: &Test) -> TestResult {
-    let input = fs::read_to_string(test.input)?;
-    let expected = fs::read_to_string(test.out)?;
+    let input = fs::read(test.input)?;
+    let expected = fs::read(test.out)?;
     Command::cargo_bin(PRG)?
         .write_stdin(input)
         .assert()
@@ -145,8 +306,8 @@ fn run_stdin(test: &Test) -> TestResult {
 
 // --------------------------------------------------
 fn run_stdin_count(test: &Test) -> TestResult {
-    let input = fs::read_to_string(test.input)?;
-    let expected = fs::read_to_string(test.out_count)?;
+    let input = fs::read(test.input)?;
+    let expected = fs::read(test.out_count)?;
     Command::cargo_bin(PRG)?
         .arg("--count")
         .write_stdin(input)
@@ -158,16 +319,16 @@ fn run_stdin_count(test: &Test) -> TestResult {
 
 // --------------------------------------------------
 fn run_outfile(test: &Test) -> TestResult {
-    let expected = fs::read_to_string(test.out)?;
+    let expected = fs::read(test.out)?;
     let outfile = NamedTempFile::new()?;
     let outpath = &outfile.path().to_str().unwrap();
     Command::cargo_bin(PRG)?
-        .args(&[test.input, outpath])
+        .args([test.input, outpath])
         .assert()
         .success()
         .stdout("");
 
-    let contents = fs::read_to_string(&outpath)?;
+    let contents = fs::read(outpath)?;
     assert_eq!(&expected, &contents);
 
     Ok(())
@@ -179,13 +340,13 @@ fn run_outfile_count(test: &Test) -> TestResult {
     let outpath = &outfile.path().to_str().unwrap();
 
     Command::cargo_bin(PRG)?
-        .args(&[test.input, outpath, "--count"])
+        .args([test.input, outpath, "--count"])
         .assert()
         .success()
         .stdout("");
 
-    let expected = fs::read_to_string(test.out_count)?;
-    let contents = fs::read_to_string(&outpath)?;
+    let expected = fs::read(test.out_count)?;
+    let contents = fs::read(outpath)?;
     assert_eq!(&expected, &contents);
 
     Ok(())
@@ -193,18 +354,18 @@ fn run_outfile_count(test: &Test) -> TestResult {
 
 // --------------------------------------------------
 fn run_stdin_outfile_count(test: &Test) -> TestResult {
-    let input = fs::read_to_string(test.input)?;
+    let input = fs::read(test.input)?;
     let outfile = NamedTempFile::new()?;
     let outpath = &outfile.path().to_str().unwrap();
 
     Command::cargo_bin(PRG)?
-        .args(&["-", outpath, "-c"])
+        .args(["-", outpath, "-c"])
         .write_stdin(input)
         .assert()
         .stdout("");
 
-    let expected = fs::read_to_string(test.out_count)?;
-    let contents = fs::read_to_string(&outpath)?;
+    let expected = fs::read(test.out_count)?;
+    let contents = fs::read(outpath)?;
     assert_eq!(&expected, &contents);
 
     Ok(())
@@ -605,3 +766,427 @@ fn t6_outfile_count() -> TestResult {
 fn t6_stdin_outfile_count() -> TestResult {
     run_stdin_outfile_count(&T6)
 }
+
+// --------------------------------------------------
+#[test]
+fn case() -> TestResult {
+    run(&CASE)
+}
+
+#[test]
+fn case_count() -> TestResult {
+    run_count(&CASE)
+}
+
+#[test]
+fn case_stdin() -> TestResult {
+    run_stdin(&CASE)
+}
+
+#[test]
+fn case_stdin_count() -> TestResult {
+    run_stdin_count(&CASE)
+}
+
+// --------------------------------------------------
+#[test]
+fn repeated() -> TestResult {
+    run_args(THREE.input, &["-d"], "tests/expected/three.txt.d.out")?;
+    run_args(CASE.input, &["--repeated"], "tests/expected/case.txt.d.out")
+}
+
+#[test]
+fn repeated_count() -> TestResult {
+    run_args(
+        THREE.input,
+        &["-c", "-d"],
+        "tests/expected/three.txt.c.d.out",
+    )?;
+    run_args(CASE.input, &["-cd"], "tests/expected/case.txt.c.d.out")
+}
+
+#[test]
+fn unique() -> TestResult {
+    run_args(THREE.input, &["-u"], "tests/expected/three.txt.u.out")?;
+    run_args(CASE.input, &["--unique"], "tests/expected/case.txt.u.out")
+}
+
+#[test]
+fn unique_count() -> TestResult {
+    run_args(
+        THREE.input,
+        &["-c", "-u"],
+        "tests/expected/three.txt.c.u.out",
+    )?;
+    run_args(CASE.input, &["-cu"], "tests/expected/case.txt.c.u.out")
+}
+
+#[test]
+fn repeated_unique() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args([THREE.input, "-d", "-u"])
+        .assert()
+        .success()
+        .stdout("");
+    Ok(())
+}
+
+#[test]
+fn all_repeated() -> TestResult {
+    run_args(THREE.input, &["-D"], "tests/expected/three.txt.D.out")?;
+    run_args(
+        CASE.input,
+        &["--all-repeated"],
+        "tests/expected/case.txt.D.out",
+    )
+}
+
+#[test]
+fn all_repeated_unique() -> TestResult {
+    run_args(
+        THREE.input,
+        &["-D", "-u"],
+        "tests/expected/three.txt.D.u.out",
+    )?;
+    run_args(CASE.input, &["-Du"], "tests/expected/case.txt.D.u.out")
+}
+
+#[test]
+fn all_repeated_methods() -> TestResult {
+    for input in [THREE.input, CASE.input] {
+        for method in ["prepend", "separate"] {
+            run_args(
+                input,
+                &[&format!("--all-repeated={}", method)],
+                &format!(
+                    "{}.D.{}.out",
+                    input.replace("inputs", "expected"),
+                    method
+                ),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn group() -> TestResult {
+    for input in [THREE.input, CASE.input] {
+        let expected = input.replace("inputs", "expected");
+        run_args(
+            input,
+            &["--group"],
+            &format!("{}.group.separate.out", expected),
+        )?;
+        for method in ["separate", "prepend", "append", "both"] {
+            run_args(
+                input,
+                &[&format!("--group={}", method)],
+                &format!("{}.group.{}.out", expected, method),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn ignore_case() -> TestResult {
+    run_args(CASE.input, &["-i"], "tests/expected/case.txt.i.out")?;
+    run_args(
+        CASE.input,
+        &["--ignore-case"],
+        "tests/expected/case.txt.i.out",
+    )
+}
+
+#[test]
+fn ignore_case_count() -> TestResult {
+    run_args(CASE.input, &["-ci"], "tests/expected/case.txt.c.i.out")
+}
+
+#[test]
+fn ignore_case_repeated() -> TestResult {
+    run_args(CASE.input, &["-i", "-d"], "tests/expected/case.txt.i.d.out")?;
+    run_args(CASE.input, &["-i", "-D"], "tests/expected/case.txt.i.D.out")
+}
+
+#[test]
+fn ignore_case_group() -> TestResult {
+    run_args(
+        CASE.input,
+        &["-i", "--group"],
+        "tests/expected/case.txt.i.group.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn log() -> TestResult {
+    run(&LOG)
+}
+
+#[test]
+fn log_count() -> TestResult {
+    run_count(&LOG)
+}
+
+#[test]
+fn log_stdin() -> TestResult {
+    run_stdin(&LOG)
+}
+
+#[test]
+fn log_stdin_count() -> TestResult {
+    run_stdin_count(&LOG)
+}
+
+// --------------------------------------------------
+#[test]
+fn skip_fields() -> TestResult {
+    run_args(
+        LOG.input,
+        &["-c", "-f", "1"],
+        "tests/expected/log.txt.c.f1.out",
+    )?;
+    run_args(
+        LOG.input,
+        &["-c", "--skip-fields=1"],
+        "tests/expected/log.txt.c.f1.out",
+    )
+}
+
+#[test]
+fn skip_fields_ignore_case() -> TestResult {
+    run_args(LOG.input, &["-cif1"], "tests/expected/log.txt.c.f1.i.out")
+}
+
+#[test]
+fn skip_chars() -> TestResult {
+    run_args(LOG.input, &["-s", "6"], "tests/expected/log.txt.s6.out")?;
+    run_args(
+        LOG.input,
+        &["--skip-chars", "6"],
+        "tests/expected/log.txt.s6.out",
+    )
+}
+
+#[test]
+fn check_chars() -> TestResult {
+    run_args(
+        LOG.input,
+        &["-f1", "-w5"],
+        "tests/expected/log.txt.f1.w5.out",
+    )?;
+    run_args(
+        LOG.input,
+        &["-w", "2", "-D"],
+        "tests/expected/log.txt.w2.D.out",
+    )
+}
+
+#[test]
+fn skip_and_check_group() -> TestResult {
+    run_args(
+        LOG.input,
+        &["-f", "1", "-s", "2", "-w", "2", "--group"],
+        "tests/expected/log.txt.f1.s2.w2.group.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn global() -> TestResult {
+    for input in [THREE.input, CASE.input, LOG.input] {
+        let expected = input.replace("inputs", "expected");
+        run_args(input, &["--global"], &format!("{}.global.out", expected))?;
+    }
+    Ok(())
+}
+
+#[test]
+fn global_count() -> TestResult {
+    for input in [THREE.input, CASE.input, LOG.input] {
+        let expected = input.replace("inputs", "expected");
+        run_args(
+            input,
+            &["--global", "-c"],
+            &format!("{}.global.c.out", expected),
+        )?;
+    }
+    Ok(())
+}
+
+#[test]
+fn global_repeated_unique() -> TestResult {
+    run_args(
+        CASE.input,
+        &["--global", "-cd"],
+        "tests/expected/case.txt.global.c.d.out",
+    )?;
+    run_args(
+        CASE.input,
+        &["--global", "-cu"],
+        "tests/expected/case.txt.global.c.u.out",
+    )
+}
+
+#[test]
+fn global_ignore_case() -> TestResult {
+    run_args(
+        CASE.input,
+        &["--global", "-i"],
+        "tests/expected/case.txt.global.i.out",
+    )
+}
+
+#[test]
+fn global_skip_fields() -> TestResult {
+    run_args(
+        LOG.input,
+        &["--global", "-f", "1"],
+        "tests/expected/log.txt.global.f1.out",
+    )
+}
+
+#[test]
+fn global_max_memory() -> TestResult {
+    for (args, out) in [(&["--global"][..], ""), (&["--global", "-c"], ".c")] {
+        let expected =
+            fs::read(format!("tests/expected/three.txt.global{}.out", out))?;
+        Command::cargo_bin(PRG)?
+            .arg(THREE.input)
+            .args(args)
+            .args(["--max-memory", "100"])
+            .assert()
+            .success()
+            .stdout(expected)
+            .stderr("uniqr: memory limit reached, comparing hashes of lines\n");
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn bytes() -> TestResult {
+    run(&BYTES)
+}
+
+#[test]
+fn bytes_count() -> TestResult {
+    run_count(&BYTES)
+}
+
+#[test]
+fn bytes_stdin() -> TestResult {
+    run_stdin(&BYTES)
+}
+
+#[test]
+fn bytes_stdin_count() -> TestResult {
+    run_stdin_count(&BYTES)
+}
+
+#[test]
+fn bytes_ignore_case() -> TestResult {
+    run_args(
+        BYTES.input,
+        &["-c", "-i"],
+        "tests/expected/bytes.txt.c.i.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn zero_terminated() -> TestResult {
+    let input = "tests/inputs/zero.bin";
+    run_args(input, &["-z"], "tests/expected/zero.bin.z.out")?;
+    run_args(
+        input,
+        &["--zero-terminated"],
+        "tests/expected/zero.bin.z.out",
+    )
+}
+
+#[test]
+fn zero_terminated_count() -> TestResult {
+    run_args(
+        "tests/inputs/zero.bin",
+        &["-zc"],
+        "tests/expected/zero.bin.z.c.out",
+    )
+}
+
+#[test]
+fn zero_terminated_group() -> TestResult {
+    run_args(
+        "tests/inputs/zero.bin",
+        &["-z", "-i", "--group=append"],
+        "tests/expected/zero.bin.z.i.group.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn keep_last() -> TestResult {
+    run_args(
+        CASE.input,
+        &["-i", "--keep", "last"],
+        "tests/expected/case.txt.i.last.out",
+    )?;
+    run_args(
+        CASE.input,
+        &["-i", "--keep=first"],
+        "tests/expected/case.txt.i.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn count_format() -> TestResult {
+    run_args(
+        THREE.input,
+        &["--count-format", "%d:"],
+        "tests/expected/three.txt.cfmt.out",
+    )?;
+    run_args(THREE.input, &["--count-format=%4d "], THREE.out_count)
+}
+
+#[test]
+fn top() -> TestResult {
+    run_args(
+        THREE.input,
+        &["--top", "3"],
+        "tests/expected/three.txt.top3.out",
+    )?;
+    run_args(
+        THREE.input,
+        &["--global", "--top=2"],
+        "tests/expected/three.txt.global.top2.out",
+    )
+}
+
+#[test]
+fn top_json() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args([THREE.input, "--global", "--top", "2", "--format", "json"])
+        .assert()
+        .success()
+        .stdout(concat!(
+            r#"{"count":4,"line":"a","percent":30.77}"#,
+            "\n",
+            r#"{"count":4,"line":"d","percent":30.77}"#,
+            "\n",
+        ));
+    Ok(())
+}
+
+#[test]
+fn top_tsv() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args([CASE.input, "-i", "--top", "2", "--format=tsv"])
+        .assert()
+        .success()
+        .stdout("3\t50.00\tb\n2\t33.33\ta\n");
+    Ok(())
+}
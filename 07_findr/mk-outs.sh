@@ -39,3 +39,28 @@ find "$IN_DIR/a" "$IN_DIR/d" -name \*.txt > "$OUT_DIR/name_txt_path_a_d.txt"
 find "$IN_DIR" -name a* > "$OUT_DIR/name_a.txt"
 find "$IN_DIR" -type f -name a* > "$OUT_DIR/type_f_name_a.txt"
 find "$IN_DIR" -type d -name a* > "$OUT_DIR/type_d_name_a.txt"
+
+find "$IN_DIR" \( -name \*.csv -o -name \*.mp3 \) ! -type l \
+    > "$OUT_DIR/expr_csv_mp3_not_l.txt"
+find "$IN_DIR" -type f -name \*.txt -o -type d -name e \
+    > "$OUT_DIR/expr_and_or.txt"
+find "$IN_DIR" -not -type d -and -name d\* > "$OUT_DIR/expr_not_and.txt"
+find "$IN_DIR" -name a -prune -o -print > "$OUT_DIR/expr_prune.txt"
+find "$IN_DIR" -name d -prune -o -type f -print \
+    > "$OUT_DIR/expr_prune_print.txt"
+find "$IN_DIR" -path \*/b/\* > "$OUT_DIR/expr_path.txt"
+find "$IN_DIR" -iname \*.CSV > "$OUT_DIR/expr_iname.txt"
+find "$IN_DIR" -regex '.*/[a-c][.].*' > "$OUT_DIR/expr_regex.txt"
+find "$IN_DIR" -type f ! -name \*.txt > "$OUT_DIR/expr_type_f_not_txt.txt"
+find "$IN_DIR" -maxdepth 1 > "$OUT_DIR/expr_maxdepth_1.txt"
+find "$IN_DIR" -mindepth 2 -maxdepth 2 \
+    > "$OUT_DIR/expr_mindepth_2_maxdepth_2.txt"
+find "$IN_DIR" -mindepth 3 -type f > "$OUT_DIR/expr_type_f_mindepth_3.txt"
+find "$IN_DIR" -maxdepth 0 > "$OUT_DIR/expr_maxdepth_0.txt"
+
+# On Windows, paths below the one given are joined with backslashes, which
+# "-path" and "-regex" patterns would have to match
+for FILE in "$OUT_DIR"/expr_*.txt; do
+    [[ "$FILE" =~ /expr_(path|regex).txt$ ]] && continue
+    sed -E 's|/|\\|2g' "$FILE" > "$FILE.windows"
+done
@@ -0,0 +1,693 @@
+use crate::EntryType::{self, *};
+use glob::{MatchOptions, Pattern};
+use regex::{Regex, RegexBuilder};
+use std::{
+    error::Error,
+    fs,
+    time::SystemTime,
+};
+use walkdir::DirEntry;
+
+type MyResult<T> = Result<T, Box<dyn Error>>;
+
+// The words that can begin an expression on the command line
+const STARTS: &[&str] = &[
+    "(", "!", "-not", "-true", "-false", "-name", "-iname", "-path", "-ipath",
+    "-regex", "-iregex", "-type", "-prune", "-print", "-maxdepth", "-mindepth",
+    "-size", "-mtime", "-newer", "-empty", "-perm", "-user", "-group",
+];
+
+#[derive(Debug)]
+pub enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    True,
+    False,
+    Name(Glob),
+    Path(Glob),
+    NameRegex(Regex),
+    PathRegex(Regex),
+    Type(Vec<EntryType>),
+    Size(Cmp, u64),
+    Mtime(Cmp, SystemTime),
+    Newer(SystemTime),
+    Empty,
+    Perm(PermMatch, u32),
+    User(u32),
+    Group(u32),
+    Prune,
+    Print,
+}
+
+// A number given as +N for more than N, -N for less, or N for exactly N
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cmp {
+    Less(u64),
+    Exactly(u64),
+    More(u64),
+}
+
+// Whether -perm wants exactly the mode, all of its bits or any of them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PermMatch {
+    Exactly,
+    All,
+    Any,
+}
+
+#[derive(Debug)]
+pub struct Glob {
+    pattern: Pattern,
+    options: MatchOptions,
+}
+
+// The options among the tests that apply to the whole walk
+#[derive(Debug, Default)]
+pub struct Walk {
+    pub min_depth: usize,
+    pub max_depth: Option<usize>,
+}
+
+// What the actions in an expression asked of the walk for one entry
+#[derive(Debug, Default)]
+pub struct Actions {
+    pub prune: bool,
+    pub print: usize,
+}
+
+// --------------------------------------------------
+impl Expr {
+    pub fn and(self, other: Expr) -> Expr {
+        Expr::And(Box::new(self), Box::new(other))
+    }
+
+    pub fn or(self, other: Expr) -> Expr {
+        Expr::Or(Box::new(self), Box::new(other))
+    }
+
+    // Evaluate from left to right, skipping whatever cannot change the
+    // outcome, as find does
+    pub fn eval(&self, entry: &DirEntry, actions: &mut Actions) -> bool {
+        match self {
+            Expr::And(a, b) => a.eval(entry, actions) && b.eval(entry, actions),
+            Expr::Or(a, b) => a.eval(entry, actions) || b.eval(entry, actions),
+            Expr::Not(e) => !e.eval(entry, actions),
+            Expr::True => true,
+            Expr::False => false,
+            Expr::Name(glob) => {
+                glob.is_match(&entry.file_name().to_string_lossy())
+            }
+            Expr::Path(glob) => glob.is_match(&entry.path().to_string_lossy()),
+            Expr::NameRegex(re) => {
+                re.is_match(&entry.file_name().to_string_lossy())
+            }
+            Expr::PathRegex(re) => re.is_match(&entry.path().to_string_lossy()),
+            Expr::Type(types) => {
+                types.iter().any(|entry_type| match entry_type {
+                    Link => entry.file_type().is_symlink(),
+                    Dir => entry.file_type().is_dir(),
+                    File => entry.file_type().is_file(),
+                })
+            }
+            Expr::Size(cmp, unit) => entry
+                .metadata()
+                .is_ok_and(|meta| cmp.test(meta.len().div_ceil(*unit))),
+            Expr::Mtime(cmp, now) => modified(entry).is_some_and(|time| {
+                // Ages are counted in whole days, any fraction dropped
+                let age = now.duration_since(time).unwrap_or_default();
+                cmp.test(age.as_secs() / 86400)
+            }),
+            Expr::Newer(than) => modified(entry).is_some_and(|t| t > *than),
+            Expr::Empty => {
+                let file_type = entry.file_type();
+                if file_type.is_dir() {
+                    fs::read_dir(entry.path())
+                        .is_ok_and(|mut entries| entries.next().is_none())
+                } else {
+                    file_type.is_file()
+                        && entry.metadata().is_ok_and(|m| m.len() == 0)
+                }
+            }
+            Expr::Perm(how, mode) => {
+                entry.metadata().ok().and_then(|m| {
+                    unix::mode(&m).map(|actual| match how {
+                        PermMatch::Exactly => actual & 0o7777 == *mode,
+                        PermMatch::All => actual & mode == *mode,
+                        PermMatch::Any => *mode == 0 || actual & mode != 0,
+                    })
+                }) == Some(true)
+            }
+            Expr::User(uid) => {
+                entry.metadata().ok().and_then(|m| unix::uid(&m)) == Some(*uid)
+            }
+            Expr::Group(gid) => {
+                entry.metadata().ok().and_then(|m| unix::gid(&m)) == Some(*gid)
+            }
+            Expr::Prune => {
+                actions.prune = true;
+                true
+            }
+            Expr::Print => {
+                actions.print += 1;
+                true
+            }
+        }
+    }
+
+    // Whether anything is printed without the implicit -print
+    pub fn has_action(&self) -> bool {
+        match self {
+            Expr::And(a, b) | Expr::Or(a, b) => {
+                a.has_action() || b.has_action()
+            }
+            Expr::Not(e) => e.has_action(),
+            Expr::Print => true,
+            _ => false,
+        }
+    }
+}
+
+// --------------------------------------------------
+impl Glob {
+    fn new(pattern: &str, ignore_case: bool) -> MyResult<Self> {
+        let pattern = Pattern::new(pattern)
+            .map_err(|e| format!("invalid pattern \"{}\": {}", pattern, e))?;
+        // Unlike in the shell, "*" matches slashes and leading dots
+        let options = MatchOptions {
+            case_sensitive: !ignore_case,
+            require_literal_separator: false,
+            require_literal_leading_dot: false,
+        };
+        Ok(Glob { pattern, options })
+    }
+
+    fn is_match(&self, text: &str) -> bool {
+        self.pattern.matches_with(text, self.options)
+    }
+}
+
+// --------------------------------------------------
+impl Cmp {
+    fn test(self, value: u64) -> bool {
+        match self {
+            Cmp::Less(n) => value < n,
+            Cmp::Exactly(n) => value == n,
+            Cmp::More(n) => value > n,
+        }
+    }
+}
+
+// --------------------------------------------------
+fn modified(entry: &DirEntry) -> Option<SystemTime> {
+    entry.metadata().ok()?.modified().ok()
+}
+
+// --------------------------------------------------
+// Parse a mode in octal or as symbolic changes to no permissions, like
+// "u=rwx,g+w"
+fn parse_mode(val: &str) -> Option<u32> {
+    if val.chars().all(|c| c.is_digit(8)) {
+        return u32::from_str_radix(val, 8).ok().filter(|&m| m <= 0o7777);
+    }
+    let mut mode = 0;
+    for clause in val.split(',') {
+        let op = clause.find(['+', '-', '='])?;
+        let (who, perms) = clause.split_at(op);
+        let mut who_mask = 0;
+        for c in who.chars() {
+            who_mask |= match c {
+                'u' => 0o4700,
+                'g' => 0o2070,
+                'o' => 0o1007,
+                'a' => 0o7777,
+                _ => return None,
+            };
+        }
+        if who_mask == 0 {
+            who_mask = 0o7777;
+        }
+        let mut bits = 0;
+        for c in perms[1..].chars() {
+            bits |= match c {
+                'r' => 0o444,
+                'w' => 0o222,
+                'x' => 0o111,
+                's' => 0o6000,
+                't' => 0o1000,
+                _ => return None,
+            };
+        }
+        bits &= who_mask;
+        match &perms[..1] {
+            "+" => mode |= bits,
+            "-" => mode &= !bits,
+            _ => mode = mode & !who_mask | bits,
+        }
+    }
+    Some(mode)
+}
+
+// --------------------------------------------------
+#[cfg(unix)]
+mod unix {
+    use std::{fs::Metadata, os::unix::fs::MetadataExt};
+    use uzers::{get_group_by_name, get_user_by_name};
+
+    pub fn mode(meta: &Metadata) -> Option<u32> {
+        Some(meta.mode())
+    }
+
+    pub fn uid(meta: &Metadata) -> Option<u32> {
+        Some(meta.uid())
+    }
+
+    pub fn gid(meta: &Metadata) -> Option<u32> {
+        Some(meta.gid())
+    }
+
+    pub fn user_id(name: &str) -> Option<u32> {
+        name.parse()
+            .ok()
+            .or_else(|| get_user_by_name(name).map(|user| user.uid()))
+    }
+
+    pub fn group_id(name: &str) -> Option<u32> {
+        name.parse()
+            .ok()
+            .or_else(|| get_group_by_name(name).map(|group| group.gid()))
+    }
+}
+
+// Without modes or owners to compare, those tests never match
+#[cfg(not(unix))]
+mod unix {
+    use std::fs::Metadata;
+
+    pub fn mode(_meta: &Metadata) -> Option<u32> {
+        None
+    }
+
+    pub fn uid(_meta: &Metadata) -> Option<u32> {
+        None
+    }
+
+    pub fn gid(_meta: &Metadata) -> Option<u32> {
+        None
+    }
+
+    pub fn user_id(name: &str) -> Option<u32> {
+        name.parse().ok()
+    }
+
+    pub fn group_id(name: &str) -> Option<u32> {
+        name.parse().ok()
+    }
+}
+
+// --------------------------------------------------
+pub fn is_start(arg: &str) -> bool {
+    STARTS.contains(&arg)
+}
+
+// --------------------------------------------------
+// Parse the words of an expression, where -a binds tighter than -o, and
+// two expressions in a row are joined by an implicit -a
+pub fn parse(args: &[String]) -> MyResult<(Expr, Walk)> {
+    let mut parser = Parser {
+        args,
+        pos: 0,
+        walk: Walk::default(),
+        now: SystemTime::now(),
+    };
+    if args.is_empty() {
+        return Ok((Expr::True, parser.walk));
+    }
+    let expr = parser.or()?;
+    match parser.next() {
+        Some(arg) => Err(format!("unexpected \"{}\"", arg).into()),
+        None => Ok((expr, parser.walk)),
+    }
+}
+
+struct Parser<'a> {
+    args: &'a [String],
+    pos: usize,
+    walk: Walk,
+    // Ages are measured from when the search began
+    now: SystemTime,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a str> {
+        self.args.get(self.pos).map(String::as_str)
+    }
+
+    fn next(&mut self) -> Option<&'a str> {
+        let arg = self.peek();
+        self.pos += 1;
+        arg
+    }
+
+    fn value(&mut self, arg: &str) -> MyResult<&'a str> {
+        self.next()
+            .ok_or_else(|| format!("missing argument to \"{}\"", arg).into())
+    }
+
+    fn invalid(arg: &str, val: &str) -> Box<dyn Error> {
+        format!("invalid argument \"{}\" to \"{}\"", val, arg).into()
+    }
+
+    // A count with an optional + or - and then the rest of the value
+    fn cmp<'v>(arg: &str, val: &'v str) -> MyResult<(Cmp, &'v str)> {
+        let (make, rest): (fn(u64) -> Cmp, _) = match val.split_at(
+            val.find(|c: char| c != '+' && c != '-')
+                .unwrap_or(val.len()),
+        ) {
+            ("+", rest) => (Cmp::More, rest),
+            ("-", rest) => (Cmp::Less, rest),
+            ("", rest) => (Cmp::Exactly, rest),
+            _ => return Err(Self::invalid(arg, val)),
+        };
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let n = rest[..digits]
+            .parse()
+            .map_err(|_| Self::invalid(arg, val))?;
+        Ok((make(n), &rest[digits..]))
+    }
+
+    fn depth(&mut self, arg: &str) -> MyResult<usize> {
+        let val = self.value(arg)?;
+        val.parse().map_err(|_| Self::invalid(arg, val))
+    }
+
+    fn or(&mut self) -> MyResult<Expr> {
+        let mut expr = self.and()?;
+        while let Some("-o" | "-or") = self.peek() {
+            self.pos += 1;
+            expr = expr.or(self.and()?);
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> MyResult<Expr> {
+        let mut expr = self.not()?;
+        loop {
+            match self.peek() {
+                None | Some("-o" | "-or" | ")") => return Ok(expr),
+                Some("-a" | "-and") => self.pos += 1,
+                _ => {}
+            }
+            expr = expr.and(self.not()?);
+        }
+    }
+
+    fn not(&mut self) -> MyResult<Expr> {
+        match self.peek() {
+            Some("!" | "-not") => {
+                self.pos += 1;
+                Ok(Expr::Not(Box::new(self.not()?)))
+            }
+            _ => self.primary(),
+        }
+    }
+
+    fn primary(&mut self) -> MyResult<Expr> {
+        let arg = match self.next() {
+            Some(arg) => arg,
+            None => return Err("expected an expression at the end".into()),
+        };
+        let expr = match arg {
+            "(" => {
+                let expr = self.or()?;
+                if self.next() != Some(")") {
+                    return Err("missing \")\"".into());
+                }
+                expr
+            }
+            ")" | "-o" | "-or" | "-a" | "-and" => {
+                return Err(format!(
+                    "expected an expression before \"{}\"",
+                    arg
+                )
+                .into())
+            }
+            "-true" => Expr::True,
+            "-false" => Expr::False,
+            "-prune" => Expr::Prune,
+            "-print" => Expr::Print,
+            "-empty" => Expr::Empty,
+            "-maxdepth" => {
+                self.walk.max_depth = Some(self.depth(arg)?);
+                Expr::True
+            }
+            "-mindepth" => {
+                self.walk.min_depth = self.depth(arg)?;
+                Expr::True
+            }
+            "-size" => {
+                let val = self.value(arg)?;
+                let (cmp, unit) = Self::cmp(arg, val)?;
+                let unit = match unit {
+                    "c" => 1,
+                    "w" => 2,
+                    "b" | "" => 512,
+                    "k" => 1 << 10,
+                    "M" => 1 << 20,
+                    "G" => 1 << 30,
+                    _ => return Err(Self::invalid(arg, val)),
+                };
+                Expr::Size(cmp, unit)
+            }
+            "-mtime" => {
+                let val = self.value(arg)?;
+                match Self::cmp(arg, val)? {
+                    (cmp, "") => Expr::Mtime(cmp, self.now),
+                    _ => return Err(Self::invalid(arg, val)),
+                }
+            }
+            "-newer" => {
+                let path = self.value(arg)?;
+                let time = fs::metadata(path)
+                    .and_then(|meta| meta.modified())
+                    .map_err(|e| format!("{}: {}", path, e))?;
+                Expr::Newer(time)
+            }
+            "-perm" => {
+                let val = self.value(arg)?;
+                let (how, mode) = match val.split_at(val.len().min(1)) {
+                    ("-", mode) => (PermMatch::All, mode),
+                    ("/", mode) => (PermMatch::Any, mode),
+                    _ => (PermMatch::Exactly, val),
+                };
+                let mode =
+                    parse_mode(mode).ok_or_else(|| Self::invalid(arg, val))?;
+                Expr::Perm(how, mode)
+            }
+            "-user" => {
+                let val = self.value(arg)?;
+                let uid = unix::user_id(val).ok_or_else(|| {
+                    format!("\"{}\" is not the name of a known user", val)
+                })?;
+                Expr::User(uid)
+            }
+            "-group" => {
+                let val = self.value(arg)?;
+                let gid = unix::group_id(val).ok_or_else(|| {
+                    format!("\"{}\" is not the name of a known group", val)
+                })?;
+                Expr::Group(gid)
+            }
+            "-name" | "-iname" => {
+                Expr::Name(Glob::new(self.value(arg)?, arg == "-iname")?)
+            }
+            "-path" | "-ipath" => {
+                Expr::Path(Glob::new(self.value(arg)?, arg == "-ipath")?)
+            }
+            "-regex" | "-iregex" => {
+                // The whole path has to match, as in find
+                let val = self.value(arg)?;
+                let re = RegexBuilder::new(&format!("^(?:{})$", val))
+                    .case_insensitive(arg == "-iregex")
+                    .build()
+                    .map_err(|_| format!("Invalid {} \"{}\"", arg, val))?;
+                Expr::PathRegex(re)
+            }
+            "-type" => {
+                let val = self.value(arg)?;
+                let types = val
+                    .split(',')
+                    .map(|c| match c {
+                        "d" => Ok(Dir),
+                        "f" => Ok(File),
+                        "l" => Ok(Link),
+                        _ => Err(format!(
+                            "invalid argument \"{}\" to \"-type\"",
+                            val
+                        )),
+                    })
+                    .collect::<Result<_, _>>()?;
+                Expr::Type(types)
+            }
+            _ => return Err(format!("unknown predicate \"{}\"", arg).into()),
+        };
+        Ok(expr)
+    }
+}
+
+// --------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::{parse, parse_mode, Actions, Cmp, Expr};
+    use walkdir::WalkDir;
+
+    fn parse_str(args: &str) -> Result<Expr, String> {
+        let args: Vec<_> = args.split(' ').map(String::from).collect();
+        parse(&args)
+            .map(|(expr, _)| expr)
+            .map_err(|e| e.to_string())
+    }
+
+    fn shape(expr: &Expr) -> String {
+        match expr {
+            Expr::And(a, b) => format!("({} & {})", shape(a), shape(b)),
+            Expr::Or(a, b) => format!("({} | {})", shape(a), shape(b)),
+            Expr::Not(e) => format!("!{}", shape(e)),
+            Expr::True => "T".to_string(),
+            Expr::False => "F".to_string(),
+            Expr::Prune => "prune".to_string(),
+            Expr::Print => "print".to_string(),
+            Expr::Type(types) => format!("{:?}", types),
+            _ => "match".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_precedence() {
+        let expr = parse_str("-true -o -false -a ! -true").unwrap();
+        assert_eq!(shape(&expr), "(T | (F & !T))");
+        let expr = parse_str("( -true -o -false ) -false").unwrap();
+        assert_eq!(shape(&expr), "((T | F) & F)");
+        let expr = parse_str("-not -not -prune -or -print").unwrap();
+        assert_eq!(shape(&expr), "(!!prune | print)");
+        let expr = parse_str("-type f,d -name *.rs").unwrap();
+        assert_eq!(shape(&expr), "([File, Dir] & match)");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for (args, msg) in [
+            ("-name", "missing argument to \"-name\""),
+            ("( -true", "missing \")\""),
+            ("-true )", "unexpected \")\""),
+            ("( )", "expected an expression before \")\""),
+            ("-o -true", "expected an expression before \"-o\""),
+            ("-true -o", "expected an expression at the end"),
+            ("!", "expected an expression at the end"),
+            ("-type x", "invalid argument \"x\" to \"-type\""),
+            ("-type f,", "invalid argument \"f,\" to \"-type\""),
+            ("-atime 1", "unknown predicate \"-atime\""),
+            ("-size 1x", "invalid argument \"1x\" to \"-size\""),
+            ("-size +-1", "invalid argument \"+-1\" to \"-size\""),
+            ("-size k", "invalid argument \"k\" to \"-size\""),
+            ("-mtime 1d", "invalid argument \"1d\" to \"-mtime\""),
+            ("-maxdepth -1", "invalid argument \"-1\" to \"-maxdepth\""),
+            ("-perm 0o755", "invalid argument \"0o755\" to \"-perm\""),
+            ("-perm u+q", "invalid argument \"u+q\" to \"-perm\""),
+            ("-perm 17777", "invalid argument \"17777\" to \"-perm\""),
+            ("-regex *", "Invalid -regex \"*\""),
+        ] {
+            assert_eq!(parse_str(args).unwrap_err(), msg, "{}", args);
+        }
+        assert!(parse_str("-name [").is_err());
+    }
+
+    #[test]
+    fn test_eval() {
+        let entry = WalkDir::new("tests/inputs/a/a.txt")
+            .into_iter()
+            .next()
+            .unwrap()
+            .unwrap();
+        let eval = |args: &str| {
+            let mut actions = Actions::default();
+            let matched = parse_str(args).unwrap().eval(&entry, &mut actions);
+            (matched, actions.prune, actions.print)
+        };
+        assert_eq!(eval("-name *.TXT"), (false, false, 0));
+        assert_eq!(eval("-iname *.TXT"), (true, false, 0));
+        assert_eq!(eval("-path tests*a/*"), (true, false, 0));
+        assert_eq!(eval("-regex .*/a/a[.]txt"), (true, false, 0));
+        assert_eq!(eval("-regex a[.]txt"), (false, false, 0));
+        assert_eq!(eval("-type d -prune"), (false, false, 0));
+        assert_eq!(eval("-type f -prune -o -print"), (true, true, 0));
+        assert_eq!(eval("-print -print"), (true, false, 2));
+        assert_eq!(eval("! -type f -o -print"), (true, false, 1));
+    }
+
+    #[test]
+    fn test_parse_walk() {
+        let args: Vec<_> = ["-mindepth", "1", "-maxdepth", "2", "-print"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        let (expr, walk) = parse(&args).unwrap();
+        assert_eq!(shape(&expr), "((T & T) & print)");
+        assert_eq!((walk.min_depth, walk.max_depth), (1, Some(2)));
+    }
+
+    #[test]
+    fn test_parse_size() {
+        for (args, cmp, unit) in [
+            ("-size 3", Cmp::Exactly(3), 512),
+            ("-size +10M", Cmp::More(10), 1 << 20),
+            ("-size -2k", Cmp::Less(2), 1024),
+            ("-size 7c", Cmp::Exactly(7), 1),
+            ("-size +1G", Cmp::More(1), 1 << 30),
+        ] {
+            match parse_str(args).unwrap() {
+                Expr::Size(c, u) => assert_eq!((c, u), (cmp, unit), "{}", args),
+                other => panic!("{:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_mode() {
+        assert_eq!(parse_mode("644"), Some(0o644));
+        assert_eq!(parse_mode("4755"), Some(0o4755));
+        assert_eq!(parse_mode("u=rw,go=r"), Some(0o644));
+        assert_eq!(parse_mode("a+x"), Some(0o111));
+        assert_eq!(parse_mode("+w"), Some(0o222));
+        assert_eq!(parse_mode("a=rwx,o-wx,g-w"), Some(0o754));
+        assert_eq!(parse_mode("u+s,o+t"), Some(0o5000));
+        assert_eq!(parse_mode("u"), None);
+        assert_eq!(parse_mode("x+r"), None);
+        assert_eq!(parse_mode("888"), None);
+    }
+
+    #[test]
+    fn test_eval_metadata() {
+        let entry = WalkDir::new("tests/inputs/a/a.txt")
+            .into_iter()
+            .next()
+            .unwrap()
+            .unwrap();
+        let matches = |args: &str| {
+            parse_str(args)
+                .unwrap()
+                .eval(&entry, &mut Actions::default())
+        };
+        // The file holds two bytes
+        assert!(matches("-size 2c"));
+        assert!(matches("-size 1"));
+        assert!(matches("-size -3c -size +1c"));
+        assert!(!matches("-size -1k"));
+        assert!(!matches("-empty"));
+        assert!(matches("-mtime +0 -o -mtime 0"));
+        assert!(!matches("-mtime -0"));
+        assert!(!matches("-newer tests/inputs/a/a.txt"));
+    }
+}
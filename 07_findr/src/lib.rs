@@ -1,8 +1,16 @@
+mod expr;
+
 use crate::EntryType::*;
 use clap::{Command, Arg};
+use expr::{Actions, Expr};
 use regex::Regex;
-use std::error::Error;
-use walkdir::{DirEntry, WalkDir};
+use std::{
+    env,
+    error::Error,
+    ffi::OsString,
+    io::{self, BufRead, Write},
+};
+use walkdir::WalkDir;
 
 type MyResult<T> = Result<T, Box<dyn Error>>;
 
@@ -16,16 +24,48 @@ enum EntryType {
 #[derive(Debug)]
 pub struct Config {
     paths: Vec<String>,
-    names: Vec<Regex>,
-    entry_types: Vec<EntryType>,
+    expr: Expr,
+    min_depth: usize,
+    max_depth: Option<usize>,
 }
 
 // --------------------------------------------------
 pub fn get_args() -> MyResult<Config> {
+    get_args_from(env::args_os())
+}
+
+// --------------------------------------------------
+pub fn get_args_from<I>(args: I) -> MyResult<Config>
+where
+    I: IntoIterator<Item = OsString>,
+{
+    // The expression begins at the first of its words and is parsed apart
+    // from the options
+    let args: Vec<_> = args.into_iter().collect();
+    let start = args
+        .iter()
+        .skip(1)
+        .position(|arg| arg.to_str().is_some_and(expr::is_start))
+        .map_or(args.len(), |i| i + 1);
+    let words: Vec<_> = args[start..]
+        .iter()
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect();
+
     let matches = Command::new("findr")
         .version("0.1.0")
         .author("Ken Youens-Clark <kyclark@gmail.com>")
         .about("Rust find")
+        .override_usage("findr [OPTIONS] [PATH]... [EXPRESSION]")
+        .after_help(
+            "EXPRESSION may use ( EXPR ), ! EXPR, -not EXPR, EXPR -a EXPR, \
+            EXPR -o EXPR, -name GLOB, -iname GLOB, -path GLOB, -ipath GLOB, \
+            -regex RE, -iregex RE, -type f|d|l, -size [+-]N[cwbkMG], \
+            -mtime [+-]DAYS, -newer FILE, -empty, -perm [-/]MODE, \
+            -user NAME, -group NAME, -maxdepth N, -mindepth N, -prune, \
+            -print, -true and -false, as in find. Without -print, the \
+            matching paths are printed.",
+        )
         .arg(
             Arg::new("paths")
                 .allow_invalid_utf8(true)
@@ -50,11 +90,12 @@ pub fn get_args() -> MyResult<Config> {
                 .short('t')
                 .long("type")
                 .help("Entry type")
-                .possible_values(&["f", "d", "l"])
+                .possible_values(["f", "d", "l"])
+                .multiple_values(true)
                 .multiple_occurrences(true)
                 .takes_value(true),
         )
-        .get_matches();
+        .try_get_matches_from(&args[..start])?;
 
     let names = matches
         .values_of_lossy("names")
@@ -71,65 +112,98 @@ pub fn get_args() -> MyResult<Config> {
 
     // clap should disallow anything but "d," "f," or "l"
     let entry_types = matches
-        .values_of_lossy("types")
+        .values_of("types")
         .map(|vals| {
-            vals.iter()
-                .map(|val| match val.as_str() {
-                    "d" => Dir,
-                    "f" => File,
-                    "l" => Link,
-                    _ => unreachable!("Invalid type"),
-                })
-                .collect()
+            vals.map(|val| match val {
+                "d" => Dir,
+                "f" => File,
+                "l" => Link,
+                _ => unreachable!("Invalid type"),
+            })
+            .collect::<Vec<_>>()
         })
         .unwrap_or_default();
 
+    // The --name and --type options must also hold
+    let (mut expr, walk) = expr::parse(&words)?;
+    if !entry_types.is_empty() {
+        expr = Expr::Type(entry_types).and(expr);
+    }
+    if let Some(names) = names.into_iter().map(Expr::NameRegex).reduce(Expr::or)
+    {
+        expr = names.and(expr);
+    }
+    if !expr.has_action() {
+        expr = expr.and(Expr::Print);
+    }
+
     Ok(Config {
         paths: matches.values_of_lossy("paths").unwrap(),
-        names,
-        entry_types,
+        expr,
+        min_depth: walk.min_depth,
+        max_depth: walk.max_depth,
     })
 }
 
 // --------------------------------------------------
 pub fn run(config: Config) -> MyResult<()> {
-    let type_filter = |entry: &DirEntry| {
-        config.entry_types.is_empty()
-            || config
-                .entry_types
-                .iter()
-                .any(|entry_type| match entry_type {
-                    Link => entry.file_type().is_symlink(),
-                    Dir => entry.file_type().is_dir(),
-                    File => entry.file_type().is_file(),
-                })
-    };
-
-    let name_filter = |entry: &DirEntry| {
-        config.names.is_empty()
-            || config
-                .names
-                .iter()
-                .any(|re| re.is_match(&entry.file_name().to_string_lossy()))
-    };
+    run_with(
+        config,
+        io::stdin().lock(),
+        io::stdout().lock(),
+        io::stderr(),
+    )
+}
 
+// --------------------------------------------------
+pub fn run_with(
+    config: Config,
+    _stdin: impl BufRead,
+    mut stdout: impl Write,
+    mut stderr: impl Write,
+) -> MyResult<()> {
     for path in &config.paths {
-        let entries = WalkDir::new(path)
-            .into_iter()
-            .filter_map(|e| match e {
+        let mut walk = WalkDir::new(path).min_depth(config.min_depth);
+        if let Some(max_depth) = config.max_depth {
+            walk = walk.max_depth(max_depth);
+        }
+        let mut entries = walk.into_iter();
+        while let Some(entry) = entries.next() {
+            let entry = match entry {
                 Err(e) => {
-                    eprintln!("{}", e);
-                    None
+                    // Keep walking even if stderr is gone
+                    let _ = writeln!(stderr, "{}", e);
+                    continue;
                 }
-                Ok(entry) => Some(entry),
-            })
-            .filter(type_filter)
-            .filter(name_filter)
-            .map(|entry| entry.path().display().to_string())
-            .collect::<Vec<_>>();
+                Ok(entry) => entry,
+            };
 
-        println!("{}", entries.join("\n"));
+            let mut actions = Actions::default();
+            config.expr.eval(&entry, &mut actions);
+            for _ in 0..actions.print {
+                writeln!(stdout, "{}", entry.path().display())?;
+            }
+            if actions.prune && entry.file_type().is_dir() {
+                entries.skip_current_dir();
+            }
+        }
     }
 
     Ok(())
 }
+
+// --------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::{get_args_from, run_with};
+    use std::{ffi::OsString, io};
+
+    #[test]
+    fn test_run_with() {
+        let args = ["findr", "tests/inputs/a", "-t", "f", "-n", "csv"];
+        let config = get_args_from(args.iter().map(OsString::from)).unwrap();
+        let mut stdout = vec![];
+        run_with(config, io::empty(), &mut stdout, vec![]).unwrap();
+        assert_eq!(stdout, b"tests/inputs/a/b/b.csv\n");
+    }
+}
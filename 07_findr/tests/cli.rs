@@ -1,7 +1,14 @@
 use assert_cmd::Command;
+use filetime::FileTime;
 use predicates::prelude::*;
 use rand::{distributions::Alphanumeric, Rng};
-use std::{borrow::Cow, fs, path::Path};
+use std::{
+    borrow::Cow,
+    fs,
+    path::Path,
+    time::{Duration, SystemTime},
+};
+use tempfile::TempDir;
 
 type TestResult = Result<(), Box<dyn std::error::Error>>;
 
@@ -39,7 +46,7 @@ fn skips_bad_dir() -> TestResult {
 #[test]
 fn dies_bad_name() -> TestResult {
     Command::cargo_bin(PRG)?
-        .args(&["--name", "*.csv"])
+        .args(["--name", "*.csv"])
         .assert()
         .failure()
         .stderr(predicate::str::contains("Invalid --name \"*.csv\""));
@@ -49,9 +56,9 @@ fn dies_bad_name() -> TestResult {
 // --------------------------------------------------
 #[test]
 fn dies_bad_type() -> TestResult {
-    let expected = "error: 'x' isn't a valid value for '--type <TYPE>...'";
+    let expected = "error: \"x\" isn't a valid value for '--type <TYPE>...'";
     Command::cargo_bin(PRG)?
-        .args(&["--type", "x"])
+        .args(["--type", "x"])
         .assert()
         .failure()
         .stderr(predicate::str::contains(expected));
@@ -67,7 +74,7 @@ fn format_file_name(expected_file: &str) -> Cow<str> {
 
 // --------------------------------------------------
 #[cfg(not(windows))]
-fn format_file_name(expected_file: &str) -> Cow<str> {
+fn format_file_name(expected_file: &str) -> Cow<'_, str> {
     // Equivalent to: Cow::Borrowed(expected_file)
     expected_file.into()
 }
@@ -281,10 +288,342 @@ fn path_g() -> TestResult {
     run(&["tests/inputs/g.csv"], "tests/expected/path_g.txt")
 }
 
+// --------------------------------------------------
+#[test]
+fn expr_csv_mp3_not_l() -> TestResult {
+    run(
+        &[
+            "tests/inputs",
+            "(",
+            "-name",
+            "*.csv",
+            "-o",
+            "-name",
+            "*.mp3",
+            ")",
+            "!",
+            "-type",
+            "l",
+        ],
+        "tests/expected/expr_csv_mp3_not_l.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn expr_and_or() -> TestResult {
+    run(
+        &[
+            "tests/inputs",
+            "-type",
+            "f",
+            "-name",
+            "*.txt",
+            "-o",
+            "-type",
+            "d",
+            "-name",
+            "e",
+        ],
+        "tests/expected/expr_and_or.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn expr_not_and() -> TestResult {
+    run(
+        &["tests/inputs", "-not", "-type", "d", "-and", "-name", "d*"],
+        "tests/expected/expr_not_and.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn expr_prune() -> TestResult {
+    run(
+        &["tests/inputs", "-name", "a", "-prune", "-o", "-print"],
+        "tests/expected/expr_prune.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn expr_prune_print() -> TestResult {
+    run(
+        &[
+            "tests/inputs",
+            "-name",
+            "d",
+            "-prune",
+            "-o",
+            "-type",
+            "f",
+            "-print",
+        ],
+        "tests/expected/expr_prune_print.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn expr_iname() -> TestResult {
+    run(
+        &["tests/inputs", "-iname", "*.CSV"],
+        "tests/expected/expr_iname.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(not(windows))]
+fn expr_path() -> TestResult {
+    run(
+        &["tests/inputs", "-path", "*/b/*"],
+        "tests/expected/expr_path.txt",
+    )
+}
+
 // --------------------------------------------------
 #[test]
 #[cfg(not(windows))]
+fn expr_regex() -> TestResult {
+    run(
+        &["tests/inputs", "-regex", ".*/[a-c][.].*"],
+        "tests/expected/expr_regex.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn expr_with_options() -> TestResult {
+    run(
+        &["tests/inputs", "-t", "f", "!", "-name", "*.txt"],
+        "tests/expected/expr_type_f_not_txt.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn expr_maxdepth_0() -> TestResult {
+    run(
+        &["tests/inputs", "-maxdepth", "0"],
+        "tests/expected/expr_maxdepth_0.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn expr_maxdepth_1() -> TestResult {
+    run(
+        &["tests/inputs", "-maxdepth", "1"],
+        "tests/expected/expr_maxdepth_1.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn expr_mindepth_2_maxdepth_2() -> TestResult {
+    run(
+        &["tests/inputs", "-mindepth", "2", "-maxdepth", "2"],
+        "tests/expected/expr_mindepth_2_maxdepth_2.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn expr_type_f_mindepth_3() -> TestResult {
+    run(
+        &["tests/inputs", "-mindepth", "3", "-type", "f"],
+        "tests/expected/expr_type_f_mindepth_3.txt",
+    )
+}
+
+// --------------------------------------------------
+// A tree whose sizes, times and modes are set by the test rather than
+// by the checkout
+fn make_tree() -> Result<TempDir, Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+    let day = Duration::from_secs(86400);
+    let now = SystemTime::now();
+    for (name, len, age) in [
+        ("empty.txt", 0, 0),
+        ("small.txt", 100, 0),
+        ("big.bin", 3 << 20, 0),
+        ("old.txt", 10, 10),
+        ("ref.txt", 10, 5),
+        ("sub/new.txt", 10, 0),
+    ] {
+        let path = dir.path().join(name);
+        fs::create_dir_all(path.parent().unwrap())?;
+        fs::File::create(&path)?.set_len(len)?;
+        let time = FileTime::from_system_time(now - day * age);
+        filetime::set_file_mtime(&path, time)?;
+    }
+    fs::create_dir(dir.path().join("emptydir"))?;
+    Ok(dir)
+}
+
+// --------------------------------------------------
+// Run on a tree and compare the sorted paths below it
+fn run_tree(dir: &TempDir, args: &[&str], expected: &[&str]) -> TestResult {
+    let cmd = Command::cargo_bin(PRG)?
+        .arg(dir.path())
+        .args(args)
+        .assert()
+        .success();
+    let stdout = String::from_utf8(cmd.get_output().stdout.clone())?;
+    let mut lines: Vec<_> = stdout
+        .lines()
+        .map(|line| {
+            let path = Path::new(line).strip_prefix(dir.path()).unwrap();
+            path.to_string_lossy().replace('\\', "/")
+        })
+        .collect();
+    lines.sort();
+    assert_eq!(lines, expected, "{:?}", args);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn expr_size() -> TestResult {
+    let dir = make_tree()?;
+    run_tree(&dir, &["-size", "+1M"], &["big.bin"])?;
+    run_tree(&dir, &["-size", "3M"], &["big.bin"])?;
+    run_tree(&dir, &["-type", "f", "-size", "100c"], &["small.txt"])?;
+    run_tree(&dir, &["-type", "f", "-size", "-1k"], &["empty.txt"])?;
+    run_tree(
+        &dir,
+        &["-type", "f", "-size", "-2"],
+        &[
+            "empty.txt",
+            "old.txt",
+            "ref.txt",
+            "small.txt",
+            "sub/new.txt",
+        ],
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn expr_mtime_newer() -> TestResult {
+    let dir = make_tree()?;
+    run_tree(&dir, &["-mtime", "+7"], &["old.txt"])?;
+    run_tree(&dir, &["-mtime", "5"], &["ref.txt"])?;
+    run_tree(
+        &dir,
+        &["-type", "f", "-mtime", "-1"],
+        &["big.bin", "empty.txt", "small.txt", "sub/new.txt"],
+    )?;
+    let newer = dir.path().join("ref.txt");
+    run_tree(
+        &dir,
+        &["-type", "f", "-newer", newer.to_str().unwrap()],
+        &["big.bin", "empty.txt", "small.txt", "sub/new.txt"],
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn expr_empty() -> TestResult {
+    let dir = make_tree()?;
+    run_tree(&dir, &["-empty"], &["empty.txt", "emptydir"])?;
+    run_tree(&dir, &["-type", "d", "!", "-empty"], &["", "sub"])
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(unix)]
+fn expr_perm() -> TestResult {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = make_tree()?;
+    for (name, mode) in [
+        ("empty.txt", 0o600),
+        ("small.txt", 0o644),
+        ("big.bin", 0o755),
+        ("old.txt", 0o444),
+        ("ref.txt", 0o644),
+        ("sub/new.txt", 0o664),
+    ] {
+        let perms = fs::Permissions::from_mode(mode);
+        fs::set_permissions(dir.path().join(name), perms)?;
+    }
+    let perm = |mode: &str, expected: &[&str]| {
+        run_tree(&dir, &["-type", "f", "-perm", mode], expected)
+    };
+    perm("644", &["ref.txt", "small.txt"])?;
+    perm("u=rw,go=r", &["ref.txt", "small.txt"])?;
+    perm("-u+x", &["big.bin"])?;
+    perm(
+        "-444",
+        &["big.bin", "old.txt", "ref.txt", "small.txt", "sub/new.txt"],
+    )?;
+    perm("/g+w,o+w", &["sub/new.txt"])
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(unix)]
+fn expr_user_group() -> TestResult {
+    use std::os::unix::fs::MetadataExt;
+
+    let dir = make_tree()?;
+    let meta = fs::metadata(dir.path())?;
+    let (uid, gid) = (meta.uid().to_string(), meta.gid().to_string());
+    let others = (meta.uid() + 1).to_string();
+    run_tree(&dir, &["-name", "small.txt", "-user", &uid], &["small.txt"])?;
+    run_tree(
+        &dir,
+        &["-name", "small.txt", "-group", &gid],
+        &["small.txt"],
+    )?;
+    run_tree(&dir, &["-user", &others], &[])
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_expr() -> TestResult {
+    for (args, expected) in [
+        (&["-foo"][..], "Found argument '-f' which wasn't expected"),
+        (&["-name", "a", "-foo"], "unknown predicate \"-foo\""),
+        (&["(", "-name", "a"], "missing \")\""),
+        (&["-type"], "missing argument to \"-type\""),
+        (&["-type", "x"], "invalid argument \"x\" to \"-type\""),
+        (&["-regex", "*"], "Invalid -regex \"*\""),
+        (&["-size", "+1X"], "invalid argument \"+1X\" to \"-size\""),
+        (&["-mtime", "x"], "invalid argument \"x\" to \"-mtime\""),
+        (
+            &["-maxdepth", "a"],
+            "invalid argument \"a\" to \"-maxdepth\"",
+        ),
+        (&["-perm", "9"], "invalid argument \"9\" to \"-perm\""),
+        (&["-user", "no such user"], "not the name of a known user"),
+        (
+            &["-group", "no such group"],
+            "not the name of a known group",
+        ),
+    ] {
+        Command::cargo_bin(PRG)?
+            .args(args)
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(expected));
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(unix)]
 fn unreadable_dir() -> TestResult {
+    // The superuser can read the directory regardless of its mode
+    if uzers::get_effective_uid() == 0 {
+        return Ok(());
+    }
+
     let dirname = "tests/inputs/cant-touch-this";
     if !Path::new(dirname).exists() {
         fs::create_dir(dirname)?;
@@ -295,7 +634,7 @@ fn unreadable_dir() -> TestResult {
     //permissions.set_mode(0o000);
 
     std::process::Command::new("chmod")
-        .args(&["000", dirname])
+        .args(["000", dirname])
         .status()
         .expect("failed");
 
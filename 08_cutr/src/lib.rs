@@ -1,16 +1,19 @@
 use crate::Extract::*;
 use clap::{Command, Arg};
+use clr_io::Error;
 use csv::{ReaderBuilder, StringRecord, WriterBuilder};
 use regex::Regex;
 use std::{
-    error::Error,
-    fs::File,
-    io::{self, BufRead, BufReader},
+    env,
+    ffi::OsString,
+    io::{self, BufRead, Write},
     num::NonZeroUsize,
     ops::Range,
 };
 
-type MyResult<T> = Result<T, Box<dyn Error>>;
+type MyResult<T> = clr_io::Result<T>;
+
+const PRG: &str = "cutr";
 type PositionList = Vec<Range<usize>>;
 
 #[derive(Debug)]
@@ -29,6 +32,14 @@ pub struct Config {
 
 // --------------------------------------------------
 pub fn get_args() -> MyResult<Config> {
+    get_args_from(env::args_os())
+}
+
+// --------------------------------------------------
+pub fn get_args_from<I>(args: I) -> MyResult<Config>
+where
+    I: IntoIterator<Item = OsString>,
+{
     let matches = Command::new("cutr")
         .version("0.1.0")
         .author("Ken Youens-Clark <kyclark@gmail.com>")
@@ -73,7 +84,7 @@ pub fn get_args() -> MyResult<Config> {
                 .help("Selected characters")
                 .conflicts_with_all(&["fields", "bytes"]),
         )
-        .get_matches();
+        .try_get_matches_from(args)?;
 
     let delimiter = matches.value_of("delimiter").unwrap();
     let delim_bytes = delimiter.as_bytes();
@@ -107,48 +118,91 @@ pub fn get_args() -> MyResult<Config> {
 
 // --------------------------------------------------
 pub fn run(config: Config) -> MyResult<()> {
+    run_with(
+        config,
+        io::stdin().lock(),
+        io::stdout().lock(),
+        io::stderr(),
+    )
+}
+
+// --------------------------------------------------
+pub fn run_with(
+    config: Config,
+    mut stdin: impl BufRead,
+    mut stdout: impl Write,
+    mut stderr: impl Write,
+) -> MyResult<()> {
+    let mut failed = false;
     for filename in &config.files {
-        match open(filename) {
-            Err(err) => eprintln!("{}: {}", filename, err),
-            Ok(file) => match &config.extract {
-                Fields(field_pos) => {
-                    let mut reader = ReaderBuilder::new()
-                        .delimiter(config.delimiter)
-                        .has_headers(false)
-                        .from_reader(file);
-
-                    let mut wtr = WriterBuilder::new()
-                        .delimiter(config.delimiter)
-                        .from_writer(io::stdout());
-
-                    for record in reader.records() {
-                        let record = record?;
-                        wtr.write_record(extract_fields(&record, field_pos))?;
-                    }
-                }
-                Bytes(byte_pos) => {
-                    for line in file.lines() {
-                        println!("{}", extract_bytes(&line?, byte_pos));
+        match clr_io::open_with(filename, &mut stdin) {
+            Err(err) => {
+                clr_io::report_to(&mut stderr, PRG, &err);
+                failed = true;
+            }
+            Ok(file) => {
+                let res = clr_io::reading(filename, &mut stdout, |out| {
+                    cut(file, &config.extract, config.delimiter, out)
+                });
+
+                // A read error skips to the next file, a write error ends
+                // the run
+                match res {
+                    Err(err @ Error::File { .. }) => {
+                        clr_io::report_to(&mut stderr, PRG, &err);
+                        failed = true;
                     }
+                    res => res?,
                 }
-                Chars(char_pos) => {
-                    for line in file.lines() {
-                        println!("{}", extract_chars(&line?, char_pos));
-                    }
-                }
-            },
+            }
         }
     }
 
+    if failed {
+        return Err(Error::Exit(1));
+    }
     Ok(())
 }
 
 // --------------------------------------------------
-fn open(filename: &str) -> MyResult<Box<dyn BufRead>> {
-    match filename {
-        "-" => Ok(Box::new(BufReader::new(io::stdin()))),
-        _ => Ok(Box::new(BufReader::new(File::open(filename)?))),
+fn cut(
+    file: impl BufRead,
+    extract: &Extract,
+    delimiter: u8,
+    out: &mut impl Write,
+) -> MyResult<()> {
+    match extract {
+        Fields(field_pos) => {
+            let mut reader = ReaderBuilder::new()
+                .delimiter(delimiter)
+                .has_headers(false)
+                .from_reader(file);
+
+            let mut wtr = WriterBuilder::new()
+                .delimiter(delimiter)
+                .from_writer(out);
+
+            for record in reader.records() {
+                let record = record.map_err(io::Error::from)?;
+                wtr.write_record(extract_fields(&record, field_pos))
+                    .map_err(io::Error::from)?;
+            }
+            wtr.flush()?;
+        }
+        Bytes(byte_pos) => {
+            for line in file.lines() {
+                let line = extract_bytes(&line?, byte_pos);
+                writeln!(out, "{}", line)?;
+            }
+        }
+        Chars(char_pos) => {
+            for line in file.lines() {
+                let line = extract_chars(&line?, char_pos);
+                writeln!(out, "{}", line)?;
+            }
+        }
     }
+    Ok(())
 }
 
 // --------------------------------------------------
@@ -159,15 +213,14 @@ fn open(filename: &str) -> MyResult<Box<dyn BufRead>> {
 // one less than the number represented by the original input.
 fn parse_index(input: &str) -> Result<usize, String> {
     let value_error = || format!("illegal list value: \"{}\"", input);
-    input
-        .starts_with('+')
-        .then(|| Err(value_error()))
-        .unwrap_or_else(|| {
-            input
-                .parse::<NonZeroUsize>()
-                .map(|n| usize::from(n) - 1)
-                .map_err(|_| value_error())
-        })
+    if input.starts_with('+') {
+        Err(value_error())
+    } else {
+        input
+            .parse::<NonZeroUsize>()
+            .map(|n| usize::from(n) - 1)
+            .map_err(|_| value_error())
+    }
 }
 
 // --------------------------------------------------
@@ -175,7 +228,6 @@ fn parse_pos(range: &str) -> MyResult<PositionList> {
     let range_re = Regex::new(r"^(\d+)-(\d+)$").unwrap();
     range
         .split(',')
-        .into_iter()
         .map(|val| {
             parse_index(val).map(|n| n..n + 1).or_else(|e| {
                 range_re.captures(val).ok_or(e).and_then(|captures| {
@@ -232,9 +284,14 @@ fn extract_chars(line: &str, char_pos: &[Range<usize>]) -> String {
 
 // --------------------------------------------------
 #[cfg(test)]
+#[allow(clippy::single_range_in_vec_init)]
 mod unit_tests {
-    use super::{extract_bytes, extract_chars, extract_fields, parse_pos};
+    use super::{
+        extract_bytes, extract_chars, extract_fields, get_args_from,
+        parse_pos, run_with,
+    };
     use csv::StringRecord;
+    use std::{ffi::OsString, io::Cursor};
 
     #[test]
     fn test_parse_pos() {
@@ -398,4 +455,14 @@ mod unit_tests {
         assert_eq!(extract_bytes("ábc", &[3..4, 2..3]), "cb".to_string());
         assert_eq!(extract_bytes("ábc", &[0..2, 5..6]), "á".to_string());
     }
+
+    #[test]
+    fn test_run_with() {
+        let args = ["cutr", "-d", ",", "-f", "2"];
+        let config = get_args_from(args.iter().map(OsString::from)).unwrap();
+        let stdin = Cursor::new("a,b,c\n1,\"2,3\",4\n");
+        let mut stdout = vec![];
+        run_with(config, stdin, &mut stdout, vec![]).unwrap();
+        assert_eq!(stdout, b"b\n\"2,3\"\n");
+    }
 }
@@ -1,4 +1,6 @@
 use assert_cmd::Command;
+#[cfg(target_os = "linux")]
+use clr_io::test_support::{unreadable_error, UNREADABLE};
 use predicates::prelude::*;
 use rand::{distributions::Alphanumeric, Rng};
 use std::fs;
@@ -35,13 +37,31 @@ fn skips_bad_file() -> TestResult {
     let bad = gen_bad_file();
     let expected = format!("{}: .* [(]os error 2[)]", bad);
     Command::cargo_bin(PRG)?
-        .args(&["-f", "1", CSV, &bad, TSV])
+        .args(["-f", "1", CSV, &bad, TSV])
         .assert()
-        .success()
+        .failure()
         .stderr(predicate::str::is_match(expected)?);
     Ok(())
 }
 
+// --------------------------------------------------
+#[test]
+#[cfg(target_os = "linux")]
+fn skips_unreadable_file() -> TestResult {
+    for (opt, expected_file) in [
+        ("-f", "tests/expected/movies1.tsv.f1.out"),
+        ("-c", "tests/expected/movies1.tsv.c1.out"),
+    ] {
+        Command::cargo_bin(PRG)?
+            .args([opt, "1", UNREADABLE, TSV])
+            .assert()
+            .failure()
+            .stderr(predicate::str::starts_with(unreadable_error(PRG)))
+            .stdout(fs::read_to_string(expected_file)?);
+    }
+    Ok(())
+}
+
 // --------------------------------------------------
 fn dies(args: &[&str], expected: &str) -> TestResult {
     Command::cargo_bin(PRG)?
@@ -110,7 +130,7 @@ fn dies_bad_delimiter() -> TestResult {
 #[test]
 fn dies_chars_bytes_fields() -> TestResult {
     Command::cargo_bin(PRG)?
-        .args(&[CSV, "-c", "1", "-f", "1", "-b", "1"])
+        .args([CSV, "-c", "1", "-f", "1", "-b", "1"])
         .assert()
         .failure();
     Ok(())
@@ -120,7 +140,7 @@ fn dies_chars_bytes_fields() -> TestResult {
 #[test]
 fn dies_bytes_fields() -> TestResult {
     Command::cargo_bin(PRG)?
-        .args(&[CSV, "-f", "1", "-b", "1"])
+        .args([CSV, "-f", "1", "-b", "1"])
         .assert()
         .failure();
     Ok(())
@@ -130,7 +150,7 @@ fn dies_bytes_fields() -> TestResult {
 #[test]
 fn dies_chars_fields() -> TestResult {
     Command::cargo_bin(PRG)?
-        .args(&[CSV, "-c", "1", "-f", "1"])
+        .args([CSV, "-c", "1", "-f", "1"])
         .assert()
         .failure();
     Ok(())
@@ -140,7 +160,7 @@ fn dies_chars_fields() -> TestResult {
 #[test]
 fn dies_chars_bytes() -> TestResult {
     Command::cargo_bin(PRG)?
-        .args(&[CSV, "-c", "1", "-b", "1"])
+        .args([CSV, "-c", "1", "-b", "1"])
         .assert()
         .failure();
     Ok(())
@@ -1,14 +1,18 @@
 use clap::{Command, Arg};
+use clr_io::Error;
 use regex::{Regex, RegexBuilder};
 use std::{
-    error::Error,
-    fs::{self, File},
-    io::{self, BufRead, BufReader},
+    env,
+    ffi::OsString,
+    fs,
+    io::{self, BufRead, Write},
     mem,
 };
 use walkdir::WalkDir;
 
-type MyResult<T> = Result<T, Box<dyn Error>>;
+type MyResult<T> = clr_io::Result<T>;
+
+const PRG: &str = "grepr";
 
 #[derive(Debug)]
 pub struct Config {
@@ -21,6 +25,14 @@ pub struct Config {
 
 // --------------------------------------------------
 pub fn get_args() -> MyResult<Config> {
+    get_args_from(env::args_os())
+}
+
+// --------------------------------------------------
+pub fn get_args_from<I>(args: I) -> MyResult<Config>
+where
+    I: IntoIterator<Item = OsString>,
+{
     let matches = Command::new("grepr")
         .version("0.1.0")
         .author("Ken Youens-Clark <kyclark@gmail.com>")
@@ -67,7 +79,7 @@ pub fn get_args() -> MyResult<Config> {
                 .help("Invert match")
                 .takes_value(false),
         )
-        .get_matches();
+        .try_get_matches_from(args)?;
 
     let pattern = matches.value_of("pattern").unwrap();
     let pattern = RegexBuilder::new(pattern)
@@ -86,39 +98,64 @@ pub fn get_args() -> MyResult<Config> {
 
 // --------------------------------------------------
 pub fn run(config: Config) -> MyResult<()> {
+    run_with(
+        config,
+        io::stdin().lock(),
+        io::stdout().lock(),
+        io::stderr(),
+    )
+}
+
+// --------------------------------------------------
+pub fn run_with(
+    config: Config,
+    mut stdin: impl BufRead,
+    mut stdout: impl Write,
+    mut stderr: impl Write,
+) -> MyResult<()> {
     // println!("{:#?}", config);
 
     let entries = find_files(&config.files, config.recursive);
     let num_files = entries.len();
-    let print = |fname: &str, val: &str| {
+    let mut print = |fname: &str, val: &str| {
         if num_files > 1 {
-            print!("{}:{}", fname, val);
+            write!(stdout, "{}:{}", fname, val)
         } else {
-            print!("{}", val);
+            write!(stdout, "{}", val)
         }
     };
 
+    let mut failed = false;
+    let mut report = |e: &Error| {
+        clr_io::report_to(&mut stderr, PRG, e);
+        failed = true;
+    };
+
     for entry in entries {
         match entry {
-            Err(e) => eprintln!("{}", e),
-            Ok(filename) => match open(&filename) {
-                Err(e) => eprintln!("{}: {}", filename, e),
+            Err(e) => report(&e),
+            Ok(filename) => match clr_io::open_with(&filename, &mut stdin) {
+                Err(e) => report(&e),
                 Ok(file) => {
                     match find_lines(
                         file,
                         &config.pattern,
                         config.invert_match,
-                    ) {
-                        Err(e) => eprintln!("{}", e),
+                    )
+                    .map_err(|e| match e {
+                        Error::Io(e) => Error::file(&filename, e),
+                        e => e,
+                    }) {
+                        Err(e) => report(&e),
                         Ok(matches) => {
                             if config.count {
                                 print(
                                     &filename,
                                     &format!("{}\n", matches.len()),
-                                );
+                                )?;
                             } else {
                                 for line in &matches {
-                                    print(&filename, line);
+                                    print(&filename, line)?;
                                 }
                             }
                         }
@@ -127,15 +164,11 @@ pub fn run(config: Config) -> MyResult<()> {
             },
         }
     }
-    Ok(())
-}
 
-// --------------------------------------------------
-fn open(filename: &str) -> MyResult<Box<dyn BufRead>> {
-    match filename {
-        "-" => Ok(Box::new(BufReader::new(io::stdin()))),
-        _ => Ok(Box::new(BufReader::new(File::open(filename)?))),
+    if failed {
+        return Err(Error::Exit(1));
     }
+    Ok(())
 }
 
 // --------------------------------------------------
@@ -183,18 +216,13 @@ fn find_files(paths: &[String], recursive: bool) -> Vec<MyResult<String>> {
                                     .to_string()));
                             }
                         } else {
-                            results.push(Err(From::from(format!(
-                                "{} is a directory",
-                                path
-                            ))));
+                            results.push(Err(Error::IsDir(path.to_string())));
                         }
                     } else if metadata.is_file() {
                         results.push(Ok(path.to_string()));
                     }
                 }
-                Err(e) => {
-                    results.push(Err(From::from(format!("{}: {}", path, e))))
-                }
+                Err(e) => results.push(Err(Error::file(path, e))),
             },
         }
     }
@@ -205,10 +233,10 @@ fn find_files(paths: &[String], recursive: bool) -> Vec<MyResult<String>> {
 // --------------------------------------------------
 #[cfg(test)]
 mod tests {
-    use super::{find_files, find_lines};
+    use super::{find_files, find_lines, get_args_from, run_with};
     use rand::{distributions::Alphanumeric, Rng};
     use regex::{Regex, RegexBuilder};
-    use std::io::Cursor;
+    use std::{ffi::OsString, io::Cursor};
 
     #[test]
     fn test_find_lines() {
@@ -254,7 +282,7 @@ mod tests {
         let files = find_files(&["./tests/inputs".to_string()], false);
         assert_eq!(files.len(), 1);
         if let Err(e) = &files[0] {
-            assert_eq!(e.to_string(), "./tests/inputs is a directory");
+            assert_eq!(e.to_string(), "./tests/inputs: Is a directory");
         }
 
         // Verify the function recurses to find four files in the directory
@@ -287,4 +315,16 @@ mod tests {
         assert_eq!(files.len(), 1);
         assert!(files[0].is_err());
     }
+
+    #[test]
+    fn test_run_with() {
+        let args = ["grepr", "-i", "the", "-", "tests/inputs"];
+        let config = get_args_from(args.iter().map(OsString::from)).unwrap();
+        let stdin = Cursor::new("The cat\na dog\nthe end\n");
+        let (mut stdout, mut stderr) = (vec![], vec![]);
+        let res = run_with(config, stdin, &mut stdout, &mut stderr);
+        assert_eq!(res.unwrap_err().exit_code(), 1);
+        assert_eq!(stdout, b"-:The cat\n-:the end\n");
+        assert_eq!(stderr, b"grepr: tests/inputs: Is a directory\n");
+    }
 }
@@ -1,13 +1,14 @@
 use crate::Column::*;
 use clap::{Command, Arg};
+use clr_io::Error;
 use std::{
     cmp::Ordering::*,
-    error::Error,
-    fs::File,
-    io::{self, BufRead, BufReader},
+    env,
+    ffi::OsString,
+    io::{self, BufRead, Write},
 };
 
-type MyResult<T> = Result<T, Box<dyn Error>>;
+type MyResult<T> = clr_io::Result<T>;
 
 #[derive(Debug)]
 enum Column<'a> {
@@ -29,6 +30,14 @@ pub struct Config {
 
 // --------------------------------------------------
 pub fn get_args() -> MyResult<Config> {
+    get_args_from(env::args_os())
+}
+
+// --------------------------------------------------
+pub fn get_args_from<I>(args: I) -> MyResult<Config>
+where
+    I: IntoIterator<Item = OsString>,
+{
     let matches = Command::new("commr")
         .version("0.1.0")
         .author("Ken Youens-Clark <kyclark@gmail.com>")
@@ -82,7 +91,7 @@ pub fn get_args() -> MyResult<Config> {
                 .default_value("\t")
                 .takes_value(true),
         )
-        .get_matches();
+        .try_get_matches_from(args)?;
 
     Ok(Config {
         file1: matches.value_of_lossy("file1").unwrap().to_string(),
@@ -97,6 +106,21 @@ pub fn get_args() -> MyResult<Config> {
 
 // --------------------------------------------------
 pub fn run(config: Config) -> MyResult<()> {
+    run_with(
+        config,
+        io::stdin().lock(),
+        io::stdout().lock(),
+        io::stderr(),
+    )
+}
+
+// --------------------------------------------------
+pub fn run_with(
+    config: Config,
+    stdin: impl BufRead,
+    mut stdout: impl Write,
+    _stderr: impl Write,
+) -> MyResult<()> {
     let file1 = &config.file1;
     let file2 = &config.file2;
 
@@ -112,10 +136,22 @@ pub fn run(config: Config) -> MyResult<()> {
         }
     };
 
-    let mut lines1 = open(file1)?.lines().filter_map(Result::ok).map(case);
-    let mut lines2 = open(file2)?.lines().filter_map(Result::ok).map(case);
+    // Only one of the files can be stdin
+    let (stdin1, stdin2): (Box<dyn BufRead>, Box<dyn BufRead>) =
+        if file1 == "-" {
+            (Box::new(stdin), Box::new(io::empty()))
+        } else {
+            (Box::new(io::empty()), Box::new(stdin))
+        };
 
-    let print = |col: Column| {
+    let mut lines1 = clr_io::open_with(file1, stdin1)?
+        .lines()
+        .map(|line| line.map(case).map_err(|e| Error::file(file1, e)));
+    let mut lines2 = clr_io::open_with(file2, stdin2)?
+        .lines()
+        .map(|line| line.map(case).map_err(|e| Error::file(file2, e)));
+
+    let mut print = |col: Column| -> MyResult<()> {
         let mut columns = vec![];
         match col {
             Col1(val) => {
@@ -145,37 +181,38 @@ pub fn run(config: Config) -> MyResult<()> {
         };
 
         if !columns.is_empty() {
-            println!("{}", columns.join(&config.delimiter));
+            writeln!(stdout, "{}", columns.join(&config.delimiter))?;
         }
+        Ok(())
     };
 
-    let mut line1 = lines1.next();
-    let mut line2 = lines2.next();
+    let mut line1 = lines1.next().transpose()?;
+    let mut line2 = lines2.next().transpose()?;
 
     while line1.is_some() || line2.is_some() {
         match (&line1, &line2) {
             (Some(val1), Some(val2)) => match val1.cmp(val2) {
                 Equal => {
-                    print(Col3(val1));
-                    line1 = lines1.next();
-                    line2 = lines2.next();
+                    print(Col3(val1))?;
+                    line1 = lines1.next().transpose()?;
+                    line2 = lines2.next().transpose()?;
                 }
                 Less => {
-                    print(Col1(val1));
-                    line1 = lines1.next();
+                    print(Col1(val1))?;
+                    line1 = lines1.next().transpose()?;
                 }
                 Greater => {
-                    print(Col2(val2));
-                    line2 = lines2.next();
+                    print(Col2(val2))?;
+                    line2 = lines2.next().transpose()?;
                 }
             },
             (Some(val1), None) => {
-                print(Col1(val1));
-                line1 = lines1.next();
+                print(Col1(val1))?;
+                line1 = lines1.next().transpose()?;
             }
             (None, Some(val2)) => {
-                print(Col2(val2));
-                line2 = lines2.next();
+                print(Col2(val2))?;
+                line2 = lines2.next().transpose()?;
             }
             _ => (),
         }
@@ -185,12 +222,19 @@ pub fn run(config: Config) -> MyResult<()> {
 }
 
 // --------------------------------------------------
-fn open(filename: &str) -> MyResult<Box<dyn BufRead>> {
-    match filename {
-        "-" => Ok(Box::new(BufReader::new(io::stdin()))),
-        _ => Ok(Box::new(BufReader::new(
-            File::open(filename)
-                .map_err(|e| format!("{}: {}", filename, e))?,
-        ))),
+#[cfg(test)]
+mod tests {
+    use super::{get_args_from, run_with};
+    use std::{ffi::OsString, fs, io::Cursor};
+
+    #[test]
+    fn test_run_with() {
+        let file1 = "tests/inputs/file1.txt";
+        let args = ["commr", "-12", file1, "-"];
+        let config = get_args_from(args.iter().map(OsString::from)).unwrap();
+        let stdin = Cursor::new(fs::read(file1).unwrap());
+        let mut stdout = vec![];
+        run_with(config, stdin, &mut stdout, vec![]).unwrap();
+        assert_eq!(stdout, fs::read(file1).unwrap());
     }
 }
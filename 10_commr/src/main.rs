@@ -1,6 +1,5 @@
 fn main() {
     if let Err(e) = commr::get_args().and_then(commr::run) {
-        eprintln!("{}", e);
-        std::process::exit(1);
+        clr_io::exit("commr", e);
     }
 }
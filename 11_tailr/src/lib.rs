@@ -1,14 +1,21 @@
 use crate::TakeValue::*;
 use clap::{Command, Arg};
+use clr_io::{
+    size::{parse_size, SizeError},
+    Error, Input,
+};
 use once_cell::sync::OnceCell;
 use regex::Regex;
 use std::{
-    error::Error,
+    env,
+    ffi::OsString,
     fs::File,
-    io::{BufRead, BufReader, Read, Seek, SeekFrom},
+    io::{self, BufRead, BufReader, Cursor, Read, Seek, SeekFrom, Write},
 };
 
-type MyResult<T> = Result<T, Box<dyn Error>>;
+type MyResult<T> = clr_io::Result<T>;
+
+const PRG: &str = "tailr";
 
 static NUM_RE: OnceCell<Regex> = OnceCell::new();
 
@@ -28,6 +35,14 @@ pub struct Config {
 
 // --------------------------------------------------
 pub fn get_args() -> MyResult<Config> {
+    get_args_from(env::args_os())
+}
+
+// --------------------------------------------------
+pub fn get_args_from<I>(args: I) -> MyResult<Config>
+where
+    I: IntoIterator<Item = OsString>,
+{
     let matches = Command::new("tailr")
         .version("0.1.0")
         .author("Ken Youens-Clark <kyclark@gmail.com>")
@@ -45,7 +60,7 @@ pub fn get_args() -> MyResult<Config> {
                 .short('n')
                 .long("lines")
                 .value_name("LINES")
-                .help("Number of lines")
+                .help("Number of lines (suffixes: b, K, M, G, KiB, KB, ...)")
                 .default_value("10"),
         )
         .arg(
@@ -54,7 +69,7 @@ pub fn get_args() -> MyResult<Config> {
                 .long("bytes")
                 .value_name("BYTES")
                 .conflicts_with("lines")
-                .help("Number of bytes"),
+                .help("Number of bytes (suffixes: b, K, M, G, KiB, KB, ...)"),
         )
         .arg(
             Arg::new("quiet")
@@ -62,7 +77,7 @@ pub fn get_args() -> MyResult<Config> {
                 .long("quiet")
                 .help("Suppress headers"),
         )
-        .get_matches();
+        .try_get_matches_from(args)?;
 
     let lines = matches
         .value_of("lines")
@@ -86,53 +101,113 @@ pub fn get_args() -> MyResult<Config> {
 
 // --------------------------------------------------
 pub fn run(config: Config) -> MyResult<()> {
+    run_with(
+        config,
+        io::stdin().lock(),
+        io::stdout().lock(),
+        io::stderr(),
+    )
+}
+
+// --------------------------------------------------
+pub fn run_with(
+    config: Config,
+    mut stdin: impl BufRead,
+    mut stdout: impl Write,
+    mut stderr: impl Write,
+) -> MyResult<()> {
     let num_files = config.files.len();
+    let mut failed = false;
     for (file_num, filename) in config.files.iter().enumerate() {
-        match File::open(filename) {
-            Err(err) => eprintln!("{}: {}", filename, err),
-            Ok(file) => {
+        match clr_io::open_input_with(filename, &mut stdin) {
+            Err(err) => {
+                clr_io::report_to(&mut stderr, PRG, &err);
+                failed = true;
+            }
+            Ok(input) => {
                 if !config.quiet && num_files > 1 {
-                    println!(
+                    writeln!(
+                        stdout,
                         "{}==> {} <==",
                         if file_num > 0 { "\n" } else { "" },
                         filename
-                    );
+                    )?;
                 }
 
-                let (total_lines, total_bytes) = count_lines_bytes(filename)?;
-                let file = BufReader::new(file);
-                if let Some(num_bytes) = &config.bytes {
-                    print_bytes(file, num_bytes, total_bytes)?;
-                } else {
-                    print_lines(file, &config.lines, total_lines)?;
+                let res = clr_io::reading(filename, &mut stdout, |out| {
+                    match input {
+                        Input::File(file) => {
+                            let totals = count_lines_bytes(filename)?;
+                            let file = BufReader::new(file);
+                            print_tail(file, &config, totals, out)
+                        }
+                        Input::Stdin(stdin) => {
+                            // Stdin can't be rewound, so hold it in memory
+                            let mut buffer = Vec::new();
+                            stdin.read_to_end(&mut buffer)?;
+                            let totals = count_reader(Cursor::new(&buffer))?;
+                            let file = Cursor::new(buffer);
+                            print_tail(file, &config, totals, out)
+                        }
+                    }
+                });
+
+                // A read error skips to the next file, a write error ends
+                // the run
+                match res {
+                    Err(err @ Error::File { .. }) => {
+                        clr_io::report_to(&mut stderr, PRG, &err);
+                        failed = true;
+                    }
+                    res => res?,
                 }
             }
         }
     }
 
+    if failed {
+        return Err(Error::Exit(1));
+    }
     Ok(())
 }
 
+// --------------------------------------------------
+fn print_tail<T: BufRead + Seek>(
+    file: T,
+    config: &Config,
+    (total_lines, total_bytes): (i64, i64),
+    out: &mut impl Write,
+) -> MyResult<()> {
+    if let Some(num_bytes) = &config.bytes {
+        print_bytes(file, num_bytes, total_bytes, out)
+    } else {
+        print_lines(file, &config.lines, total_lines, out)
+    }
+}
+
 // --------------------------------------------------
 fn parse_num(val: &str) -> MyResult<TakeValue> {
-    let num_re =
-        NUM_RE.get_or_init(|| Regex::new(r"^([+-])?(\d+)$").unwrap());
-
-    match num_re.captures(val) {
-        Some(caps) => {
-            let sign = caps.get(1).map_or("-", |m| m.as_str());
-            let num = format!("{}{}", sign, caps.get(2).unwrap().as_str());
-            if let Ok(val) = num.parse() {
-                if sign == "+" && val == 0 {
-                    Ok(PlusZero)
-                } else {
-                    Ok(TakeNum(val))
-                }
-            } else {
-                Err(From::from(val))
-            }
-        }
-        _ => Err(From::from(val)),
+    let num_re = NUM_RE
+        .get_or_init(|| Regex::new(r"^([+-])?(\d+[[:alpha:]]*)$").unwrap());
+
+    let caps = num_re.captures(val).ok_or_else(|| Error::from(val))?;
+    let sign = caps.get(1).map_or("-", |m| m.as_str());
+    let num = match parse_size(caps.get(2).unwrap().as_str()) {
+        Ok(num) => num,
+        Err(SizeError::Invalid) => return Err(From::from(val)),
+        Err(e) => return Err(Error::Msg(format!("{}: {}", val, e))),
+    };
+
+    // Unsigned counts are taken from the end, like a leading "-"
+    let res = if sign == "+" {
+        i64::try_from(num).ok()
+    } else {
+        0i64.checked_sub_unsigned(num)
+    };
+    match res {
+        Some(0) if sign == "+" => Ok(PlusZero),
+        Some(num) => Ok(TakeNum(num)),
+        None => Err(Error::Msg(format!("{}: {}", val, SizeError::TooLarge))),
     }
 }
 
@@ -165,7 +240,12 @@ fn parse_num(val: &str) -> MyResult<TakeValue> {
 
 // --------------------------------------------------
 fn count_lines_bytes(filename: &str) -> MyResult<(i64, i64)> {
-    let mut file = BufReader::new(File::open(filename)?);
+    let file = File::open(filename).map_err(|e| Error::file(filename, e))?;
+    count_reader(BufReader::new(file))
+}
+
+// --------------------------------------------------
+fn count_reader(mut file: impl BufRead) -> MyResult<(i64, i64)> {
     let mut num_lines = 0;
     let mut num_bytes = 0;
     let mut buf = Vec::new();
@@ -186,13 +266,14 @@ fn print_bytes<T: Read + Seek>(
     mut file: T,
     num_bytes: &TakeValue,
     total_bytes: i64,
+    out: &mut impl Write,
 ) -> MyResult<()> {
     if let Some(start) = get_start_index(num_bytes, total_bytes) {
         file.seek(SeekFrom::Start(start))?;
         let mut buffer = Vec::new();
         file.read_to_end(&mut buffer)?;
         if !buffer.is_empty() {
-            print!("{}", String::from_utf8_lossy(&buffer));
+            write!(out, "{}", String::from_utf8_lossy(&buffer))?;
         }
     }
 
@@ -204,6 +285,7 @@ fn print_lines(
     mut file: impl BufRead,
     num_lines: &TakeValue,
     total_lines: i64,
+    out: &mut impl Write,
 ) -> MyResult<()> {
     if let Some(start) = get_start_index(num_lines, total_lines) {
         let mut line_num = 0;
@@ -214,7 +296,7 @@ fn print_lines(
                 break;
             }
             if line_num >= start {
-                print!("{}", String::from_utf8_lossy(&buf));
+                write!(out, "{}", String::from_utf8_lossy(&buf))?;
             }
             line_num += 1;
             buf.clear();
@@ -249,8 +331,10 @@ fn get_start_index(take_val: &TakeValue, total: i64) -> Option<u64> {
 #[cfg(test)]
 mod tests {
     use super::{
-        count_lines_bytes, get_start_index, parse_num, TakeValue::*,
+        count_lines_bytes, get_args_from, get_start_index, parse_num, run_with,
+        TakeValue::*,
     };
+    use std::{ffi::OsString, io::Cursor};
 
     #[test]
     fn test_count_lines_bytes() {
@@ -351,5 +435,32 @@ mod tests {
         let res = parse_num("foo");
         assert!(res.is_err());
         assert_eq!(res.unwrap_err().to_string(), "foo");
+
+        // Multiplier suffixes
+        assert_eq!(parse_num("2K").unwrap(), TakeNum(-2048));
+        assert_eq!(parse_num("+1KB").unwrap(), TakeNum(1000));
+        assert_eq!(parse_num("-1MiB").unwrap(), TakeNum(-(1 << 20)));
+        assert_eq!(parse_num("3b").unwrap(), TakeNum(-1536));
+        assert_eq!(parse_num("+0G").unwrap(), PlusZero);
+        assert_eq!(parse_num("1x").unwrap_err().to_string(), "1x");
+
+        // Values that overflow say so
+        let res = parse_num("+8E");
+        assert_eq!(res.unwrap_err().to_string(), "+8E: value too large");
+        let res = parse_num("99999999999999999999");
+        assert_eq!(
+            res.unwrap_err().to_string(),
+            "99999999999999999999: value too large"
+        );
+    }
+
+    #[test]
+    fn test_run_with() {
+        let args = ["tailr", "-n", "2", "-"];
+        let config = get_args_from(args.iter().map(OsString::from)).unwrap();
+        let stdin = Cursor::new("one\ntwo\nthree\n");
+        let mut stdout = vec![];
+        run_with(config, stdin, &mut stdout, vec![]).unwrap();
+        assert_eq!(stdout, b"two\nthree\n");
     }
 }
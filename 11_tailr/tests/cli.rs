@@ -1,4 +1,6 @@
 use assert_cmd::Command;
+#[cfg(target_os = "linux")]
+use clr_io::test_support::{unreadable_error, UNREADABLE};
 use predicates::prelude::*;
 use rand::{distributions::Alphanumeric, Rng};
 use std::fs::{self, File};
@@ -49,7 +51,7 @@ fn dies_bad_bytes() -> TestResult {
     let bad = random_string();
     let expected = format!("illegal byte count -- {}", &bad);
     Command::cargo_bin(PRG)?
-        .args(&["-c", &bad, EMPTY])
+        .args(["-c", &bad, EMPTY])
         .assert()
         .failure()
         .stderr(predicate::str::contains(expected));
@@ -63,7 +65,7 @@ fn dies_bad_lines() -> TestResult {
     let bad = random_string();
     let expected = format!("illegal line count -- {}", &bad);
     Command::cargo_bin(PRG)?
-        .args(&["-n", &bad, EMPTY])
+        .args(["-n", &bad, EMPTY])
         .assert()
         .failure()
         .stderr(predicate::str::contains(expected));
@@ -78,7 +80,7 @@ fn dies_bytes_and_lines() -> TestResult {
                used with '--bytes <BYTES>'";
 
     Command::cargo_bin(PRG)?
-        .args(&["-n", "1", "-c", "2"])
+        .args(["-n", "1", "-c", "2"])
         .assert()
         .failure()
         .stderr(predicate::str::contains(msg));
@@ -92,13 +94,34 @@ fn skips_bad_file() -> TestResult {
     let bad = gen_bad_file();
     let expected = format!("{}: .* [(]os error 2[)]", bad);
     Command::cargo_bin(PRG)?
-        .args(&[ONE, &bad, TWO])
+        .args([ONE, &bad, TWO])
         .assert()
         .stderr(predicate::str::is_match(expected)?);
 
     Ok(())
 }
 
+// --------------------------------------------------
+#[test]
+#[cfg(target_os = "linux")]
+fn skips_unreadable_file() -> TestResult {
+    let one = fs::read_to_string(ONE)?;
+    for (args, stdout) in [
+        (&[][..], one.as_str()),
+        (&["-c", "5"], "dś.\n"),
+        (&["-n", "+1"], &one),
+    ] {
+        Command::cargo_bin(PRG)?
+            .args(args)
+            .args(["-q", UNREADABLE, ONE])
+            .assert()
+            .failure()
+            .stderr(predicate::str::starts_with(unreadable_error(PRG)))
+            .stdout(stdout.to_string());
+    }
+    Ok(())
+}
+
 // --------------------------------------------------
 fn run(args: &[&str], expected_file: &str) -> TestResult {
     // Extra work here due to lossy UTF
@@ -115,6 +138,39 @@ fn run(args: &[&str], expected_file: &str) -> TestResult {
     Ok(())
 }
 
+// --------------------------------------------------
+fn run_stdin(
+    args: &[&str],
+    input_file: &str,
+    expected_file: &str,
+) -> TestResult {
+    let input = fs::read(input_file)?;
+    let expected = fs::read(expected_file)?;
+    Command::cargo_bin(PRG)?
+        .args(args)
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn stdin_n3() -> TestResult {
+    run_stdin(&["-n", "3", "-"], TEN, "tests/expected/ten.txt.n3.out")
+}
+
+#[test]
+fn stdin_n_plus_2() -> TestResult {
+    run_stdin(&["-n", "+2", "-"], TEN, "tests/expected/ten.txt.n+2.out")
+}
+
+#[test]
+fn stdin_c8() -> TestResult {
+    run_stdin(&["-c", "8", "-"], TEN, "tests/expected/ten.txt.c8.out")
+}
+
 // --------------------------------------------------
 #[test]
 fn empty() -> TestResult {
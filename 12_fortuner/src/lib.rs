@@ -3,10 +3,11 @@ use rand::prelude::SliceRandom;
 use rand::{rngs::StdRng, SeedableRng};
 use regex::{Regex, RegexBuilder};
 use std::{
+    env,
     error::Error,
-    ffi::OsStr,
+    ffi::{OsStr, OsString},
     fs::{self, File},
-    io::{BufRead, BufReader},
+    io::{self, BufRead, BufReader, Write},
     path::PathBuf,
 };
 use walkdir::WalkDir;
@@ -28,6 +29,14 @@ pub struct Fortune {
 
 // --------------------------------------------------
 pub fn get_args() -> MyResult<Config> {
+    get_args_from(env::args_os())
+}
+
+// --------------------------------------------------
+pub fn get_args_from<I>(args: I) -> MyResult<Config>
+where
+    I: IntoIterator<Item = OsString>,
+{
     let matches = Command::new("fortuner")
         .version("0.1.0")
         .author("Ken Youens-Clark <kyclark@gmail.com>")
@@ -61,7 +70,7 @@ pub fn get_args() -> MyResult<Config> {
                 .long("seed")
                 .help("Random seed"),
         )
-        .get_matches();
+        .try_get_matches_from(args)?;
 
     let pattern = matches
         .value_of("pattern")
@@ -82,6 +91,21 @@ pub fn get_args() -> MyResult<Config> {
 
 // --------------------------------------------------
 pub fn run(config: Config) -> MyResult<()> {
+    run_with(
+        config,
+        io::stdin().lock(),
+        io::stdout().lock(),
+        io::stderr(),
+    )
+}
+
+// --------------------------------------------------
+pub fn run_with(
+    config: Config,
+    _stdin: impl BufRead,
+    mut stdout: impl Write,
+    mut stderr: impl Write,
+) -> MyResult<()> {
     let files = find_files(&config.sources)?;
     let fortunes = read_fortunes(&files)?;
 
@@ -91,19 +115,20 @@ pub fn run(config: Config) -> MyResult<()> {
             .iter()
             .filter(|fortune| pattern.is_match(&fortune.text))
         {
-            if prev_source.as_ref().map_or(true, |s| s != &fortune.source) {
-                eprintln!("({})\n%", fortune.source);
+            if prev_source.as_ref() != Some(&fortune.source) {
+                writeln!(stderr, "({})\n%", fortune.source)?;
                 prev_source = Some(fortune.source.clone());
             }
-            println!("{}\n%", fortune.text);
+            writeln!(stdout, "{}\n%", fortune.text)?;
         }
     } else {
-        println!(
+        writeln!(
+            stdout,
             "{}",
             pick_fortune(&fortunes, config.seed)
                 .or_else(|| Some("No fortunes found".to_string()))
                 .unwrap()
-        );
+        )?;
     }
 
     Ok(())
@@ -153,7 +178,7 @@ fn read_fortunes(paths: &[PathBuf]) -> MyResult<Vec<Fortune>> {
             format!("{}: {}", path.to_string_lossy().into_owned(), e)
         })?;
 
-        for line in BufReader::new(file).lines().filter_map(Result::ok) {
+        for line in BufReader::new(file).lines().map_while(Result::ok) {
             if line == "%" {
                 if !buffer.is_empty() {
                     fortunes.push(Fortune {
@@ -186,9 +211,10 @@ fn pick_fortune(fortunes: &[Fortune], seed: Option<u64>) -> Option<String> {
 #[cfg(test)]
 mod tests {
     use super::{
-        find_files, parse_u64, pick_fortune, read_fortunes, Fortune,
+        find_files, get_args_from, parse_u64, pick_fortune, read_fortunes,
+        run_with, Fortune,
     };
-    use std::path::PathBuf;
+    use std::{ffi::OsString, io, path::PathBuf};
 
     #[test]
     fn test_parse_u64() {
@@ -214,7 +240,7 @@ mod tests {
         let files = res.unwrap();
         assert_eq!(files.len(), 1);
         assert_eq!(
-            files.get(0).unwrap().to_string_lossy(),
+            files.first().unwrap().to_string_lossy(),
             "./tests/inputs/jokes"
         );
 
@@ -229,7 +255,7 @@ mod tests {
         // Check number and order of files
         let files = res.unwrap();
         assert_eq!(files.len(), 5);
-        let first = files.get(0).unwrap().display().to_string();
+        let first = files.first().unwrap().display().to_string();
         assert!(first.contains("ascii-art"));
         let last = files.last().unwrap().display().to_string();
         assert!(last.contains("quotes"));
@@ -308,4 +334,18 @@ mod tests {
             "Neckties strangle clear thinking.".to_string()
         );
     }
+
+    #[test]
+    fn test_run_with() {
+        let args = ["fortuner", "tests/inputs/jokes", "-m", "lichen"];
+        let config = get_args_from(args.iter().map(OsString::from)).unwrap();
+        let (mut stdout, mut stderr) = (vec![], vec![]);
+        run_with(config, io::empty(), &mut stdout, &mut stderr).unwrap();
+        assert_eq!(stderr, b"(jokes)\n%\n");
+        assert_eq!(
+            stdout,
+            b"Q: Why did the fungus and the alga marry?\n\
+              A: Because they took a lichen to each other!\n%\n"
+        );
+    }
 }
@@ -2,7 +2,13 @@ use ansi_term::Style;
 use chrono::{Datelike, Local, NaiveDate};
 use clap::{Command, Arg};
 use itertools::izip;
-use std::{error::Error, str::FromStr};
+use std::{
+    env,
+    error::Error,
+    ffi::OsString,
+    io::{self, BufRead, Write},
+    str::FromStr,
+};
 
 #[derive(Debug)]
 pub struct Config {
@@ -31,6 +37,14 @@ const MONTH_NAMES: [&str; 12] = [
 
 // --------------------------------------------------
 pub fn get_args() -> MyResult<Config> {
+    get_args_from(env::args_os())
+}
+
+// --------------------------------------------------
+pub fn get_args_from<I>(args: I) -> MyResult<Config>
+where
+    I: IntoIterator<Item = OsString>,
+{
     let matches = Command::new("calr")
         .version("0.1.0")
         .author("Ken Youens-Clark <kyclark@gmail.com>")
@@ -56,12 +70,12 @@ pub fn get_args() -> MyResult<Config> {
                 .value_name("YEAR")
                 .help("Year (1-9999)"),
         )
-        .get_matches();
+        .try_get_matches_from(args)?;
 
     let mut month = matches.value_of("month").map(parse_month).transpose()?;
     let mut year = matches.value_of("year").map(parse_year).transpose()?;
 
-    let today = Local::today();
+    let today = Local::now().date_naive();
     if matches.is_present("show_current_year") {
         month = None;
         year = Some(today.year());
@@ -73,21 +87,35 @@ pub fn get_args() -> MyResult<Config> {
     Ok(Config {
         month,
         year: year.unwrap_or_else(|| today.year()),
-        today: today.naive_local(),
+        today,
     })
 }
 
 // --------------------------------------------------
 pub fn run(config: Config) -> MyResult<()> {
+    run_with(
+        config,
+        io::stdin().lock(),
+        io::stdout().lock(),
+        io::stderr(),
+    )
+}
+
+// --------------------------------------------------
+pub fn run_with(
+    config: Config,
+    _stdin: impl BufRead,
+    mut stdout: impl Write,
+    _stderr: impl Write,
+) -> MyResult<()> {
     match config.month {
         Some(month) => {
             let lines = format_month(config.year, month, true, config.today);
-            println!("{}", lines.join("\n"));
+            writeln!(stdout, "{}", lines.join("\n"))?;
         }
         None => {
-            println!("{:>32}", config.year);
+            writeln!(stdout, "{:>32}", config.year)?;
             let months: Vec<_> = (1..=12)
-                .into_iter()
                 .map(|month| {
                     format_month(config.year, month, false, config.today)
                 })
@@ -96,10 +124,10 @@ pub fn run(config: Config) -> MyResult<()> {
             for (i, chunk) in months.chunks(3).enumerate() {
                 if let [m1, m2, m3] = chunk {
                     for lines in izip!(m1, m2, m3) {
-                        println!("{}{}{}", lines.0, lines.1, lines.2);
+                        writeln!(stdout, "{}{}{}", lines.0, lines.1, lines.2)?;
                     }
                     if i < 3 {
-                        println!();
+                        writeln!(stdout)?;
                     }
                 }
             }
@@ -173,7 +201,7 @@ fn last_day_in_month(year: i32, month: u32) -> NaiveDate {
         (year, month + 1)
     };
     // ...is preceded by the last day of the original month
-    NaiveDate::from_ymd(y, m, 1).pred()
+    NaiveDate::from_ymd_opt(y, m, 1).and_then(|d| d.pred_opt()).unwrap()
 }
 
 // --------------------------------------------------
@@ -183,9 +211,8 @@ fn format_month(
     print_year: bool,
     today: NaiveDate,
 ) -> Vec<String> {
-    let first = NaiveDate::from_ymd(year, month, 1);
+    let first = NaiveDate::from_ymd_opt(year, month, 1).unwrap();
     let mut days: Vec<String> = (1..first.weekday().number_from_sunday())
-        .into_iter()
         .map(|_| "  ".to_string()) // two spaces
         .collect();
 
@@ -194,7 +221,7 @@ fn format_month(
     };
 
     let last = last_day_in_month(year, month);
-    days.extend((first.day()..=last.day()).into_iter().map(|num| {
+    days.extend((first.day()..=last.day()).map(|num| {
         let fmt = format!("{:>2}", num);
         if is_today(num) {
             Style::new().reverse().paint(fmt).to_string()
@@ -235,9 +262,11 @@ fn format_month(
 #[cfg(test)]
 mod tests {
     use super::{
-        format_month, last_day_in_month, parse_int, parse_month, parse_year,
+        format_month, get_args_from, last_day_in_month, parse_int,
+        parse_month, parse_year, run_with,
     };
     use chrono::NaiveDate;
+    use std::{ffi::OsString, io};
 
     #[test]
     fn test_parse_int() {
@@ -321,7 +350,7 @@ mod tests {
 
     #[test]
     fn test_format_month() {
-        let today = NaiveDate::from_ymd(0, 1, 1);
+        let today = NaiveDate::from_ymd_opt(0, 1, 1).unwrap();
         let leap_february = vec![
             "   February 2020      ",
             "Su Mo Tu We Th Fr Sa  ",
@@ -356,7 +385,7 @@ mod tests {
             "25 26 27 28 29 30     ",
             "                      ",
         ];
-        let today = NaiveDate::from_ymd(2021, 4, 7);
+        let today = NaiveDate::from_ymd_opt(2021, 4, 7).unwrap();
         assert_eq!(format_month(2021, 4, true, today), april_hl);
     }
 
@@ -364,15 +393,26 @@ mod tests {
     fn test_last_day_in_month() {
         assert_eq!(
             last_day_in_month(2020, 1),
-            NaiveDate::from_ymd(2020, 1, 31)
+            NaiveDate::from_ymd_opt(2020, 1, 31).unwrap()
         );
         assert_eq!(
             last_day_in_month(2020, 2),
-            NaiveDate::from_ymd(2020, 2, 29)
+            NaiveDate::from_ymd_opt(2020, 2, 29).unwrap()
         );
         assert_eq!(
             last_day_in_month(2020, 4),
-            NaiveDate::from_ymd(2020, 4, 30)
+            NaiveDate::from_ymd_opt(2020, 4, 30).unwrap()
         );
     }
+
+    #[test]
+    fn test_run_with() {
+        let args = ["calr", "-m", "2", "2020"];
+        let config = get_args_from(args.iter().map(OsString::from)).unwrap();
+        let mut stdout = vec![];
+        run_with(config, io::empty(), &mut stdout, vec![]).unwrap();
+        let stdout = String::from_utf8(stdout).unwrap();
+        assert!(stdout.starts_with("   February 2020      \n"));
+        assert!(stdout.contains("\n23 24 25 26 27 28 29  \n"));
+    }
 }
@@ -1,6 +1,5 @@
 fn main() {
     if let Err(e) = calr::get_args().and_then(calr::run) {
-        eprintln!("{}", e);
-        std::process::exit(1);
+        clr_io::exit_boxed(e);
     }
 }
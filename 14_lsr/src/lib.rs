@@ -3,7 +3,15 @@ mod owner;
 use chrono::{DateTime, Local};
 use clap::{Command, Arg};
 use owner::Owner;
-use std::{error::Error, fs, os::unix::fs::MetadataExt, path::PathBuf};
+use std::{
+    env,
+    error::Error,
+    ffi::OsString,
+    fs,
+    io::{self, BufRead, Write},
+    os::unix::fs::MetadataExt,
+    path::PathBuf,
+};
 use tabular::{Row, Table};
 use users::{get_group_by_gid, get_user_by_uid};
 
@@ -18,7 +26,15 @@ pub struct Config {
 
 // --------------------------------------------------
 pub fn get_args() -> MyResult<Config> {
-    let matches = App::new("lsr")
+    get_args_from(env::args_os())
+}
+
+// --------------------------------------------------
+pub fn get_args_from<I>(args: I) -> MyResult<Config>
+where
+    I: IntoIterator<Item = OsString>,
+{
+    let matches = Command::new("lsr")
         .version("0.1.0")
         .author("Ken Youens-Clark <kyclark@gmail.com>")
         .about("Rust ls")
@@ -34,17 +50,17 @@ pub fn get_args() -> MyResult<Config> {
             Arg::new("long")
                 .takes_value(false)
                 .help("Long listing")
-                .short("l")
+                .short('l')
                 .long("long"),
         )
         .arg(
             Arg::new("all")
                 .takes_value(false)
                 .help("Show all files")
-                .short("a")
+                .short('a')
                 .long("all"),
         )
-        .get_matches();
+        .try_get_matches_from(args)?;
 
     Ok(Config {
         paths: matches.values_of_lossy("paths").unwrap(),
@@ -55,31 +71,50 @@ pub fn get_args() -> MyResult<Config> {
 
 // --------------------------------------------------
 pub fn run(config: Config) -> MyResult<()> {
-    let paths = find_files(&config.paths, config.show_hidden)?;
+    run_with(
+        config,
+        io::stdin().lock(),
+        io::stdout().lock(),
+        io::stderr(),
+    )
+}
+
+// --------------------------------------------------
+pub fn run_with(
+    config: Config,
+    _stdin: impl BufRead,
+    mut stdout: impl Write,
+    mut stderr: impl Write,
+) -> MyResult<()> {
+    let paths = find_files(&config.paths, config.show_hidden, &mut stderr)?;
     //println!("{:?}", paths);
     if config.long {
-        println!("{}", format_output(&paths)?);
+        writeln!(stdout, "{}", format_output(&paths)?)?;
     } else {
         for path in paths {
-            println!("{}", path.display());
+            writeln!(stdout, "{}", path.display())?;
         }
     }
     Ok(())
 }
 
 // --------------------------------------------------
-fn find_files(paths: &[String], show_hidden: bool) -> MyResult<Vec<PathBuf>> {
+fn find_files(
+    paths: &[String],
+    show_hidden: bool,
+    stderr: &mut impl Write,
+) -> MyResult<Vec<PathBuf>> {
     let mut results = vec![];
     for name in paths {
         match fs::metadata(name) {
-            Err(e) => eprintln!("{}: {}", name, e),
+            Err(e) => writeln!(stderr, "{}: {}", name, e)?,
             Ok(meta) => {
                 if meta.is_dir() {
                     for entry in fs::read_dir(name)? {
                         let entry = entry?;
                         let path = entry.path();
                         let is_hidden =
-                            path.file_name().map_or(false, |file_name| {
+                            path.file_name().is_some_and(|file_name| {
                                 file_name.to_string_lossy().starts_with('.')
                             });
                         if !is_hidden || show_hidden {
@@ -162,13 +197,17 @@ fn mk_triple(mode: u32, owner: Owner) -> String {
 // --------------------------------------------------
 #[cfg(test)]
 mod test {
-    use super::{find_files, format_mode, format_output, mk_triple, Owner};
-    use std::path::PathBuf;
+    use super::{
+        find_files, format_mode, format_output, get_args_from, mk_triple,
+        run_with, Owner,
+    };
+    use std::{ffi::OsString, io, path::PathBuf};
 
     #[test]
     fn test_find_files() {
         // Find all non-hidden entries in a directory
-        let res = find_files(&["tests/inputs".to_string()], false);
+        let res =
+            find_files(&["tests/inputs".to_string()], false, &mut io::sink());
         assert!(res.is_ok());
         let mut filenames: Vec<_> = res
             .unwrap()
@@ -187,7 +226,11 @@ mod test {
         );
 
         // Any existing file should be found even if hidden
-        let res = find_files(&["tests/inputs/.hidden".to_string()], false);
+        let res = find_files(
+            &["tests/inputs/.hidden".to_string()],
+            false,
+            &mut io::sink(),
+        );
         assert!(res.is_ok());
         let filenames: Vec<_> = res
             .unwrap()
@@ -203,6 +246,7 @@ mod test {
                 "tests/inputs/dir".to_string(),
             ],
             false,
+            &mut io::sink(),
         );
         assert!(res.is_ok());
         let mut filenames: Vec<_> = res
@@ -220,7 +264,8 @@ mod test {
     #[test]
     fn test_find_files_hidden() {
         // Find all entries in a directory including hidden
-        let res = find_files(&["tests/inputs".to_string()], true);
+        let res =
+            find_files(&["tests/inputs".to_string()], true, &mut io::sink());
         assert!(res.is_ok());
         let mut filenames: Vec<_> = res
             .unwrap()
@@ -247,9 +292,9 @@ mod test {
         expected_size: Option<&str>,
     ) {
         let parts: Vec<_> = line.split_whitespace().collect();
-        assert!(parts.len() > 0 && parts.len() <= 10);
+        assert!(!parts.is_empty() && parts.len() <= 10);
 
-        let perms = parts.get(0).unwrap();
+        let perms = parts.first().unwrap();
         assert_eq!(perms, &expected_perms);
 
         if let Some(size) = expected_size {
@@ -275,7 +320,7 @@ mod test {
         assert_eq!(lines.len(), 1);
 
         let line1 = lines.first().unwrap();
-        long_match(&line1, bustle_path, "-rw-r--r--", Some("193"));
+        long_match(line1, bustle_path, "-rw-r--r--", Some("193"));
     }
 
     #[test]
@@ -294,14 +339,14 @@ mod test {
 
         let empty_line = lines.remove(0);
         long_match(
-            &empty_line,
+            empty_line,
             "tests/inputs/empty.txt",
             "-rw-r--r--",
             Some("0"),
         );
 
         let dir_line = lines.remove(0);
-        long_match(&dir_line, "tests/inputs/dir", "drwxr-xr-x", None);
+        long_match(dir_line, "tests/inputs/dir", "drwxr-xr-x", None);
     }
 
     #[test]
@@ -317,4 +362,14 @@ mod test {
         assert_eq!(format_mode(0o755), "rwxr-xr-x");
         assert_eq!(format_mode(0o421), "r---w---x");
     }
+
+    #[test]
+    fn test_run_with() {
+        let args = ["lsr", "tests/inputs/bustle.txt", "no-such-file"];
+        let config = get_args_from(args.iter().map(OsString::from)).unwrap();
+        let (mut stdout, mut stderr) = (vec![], vec![]);
+        run_with(config, io::empty(), &mut stdout, &mut stderr).unwrap();
+        assert_eq!(stdout, b"tests/inputs/bustle.txt\n");
+        assert!(stderr.starts_with(b"no-such-file: "));
+    }
 }
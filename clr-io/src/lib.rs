@@ -0,0 +1,360 @@
+//! Input handling, error reporting and count parsing shared by the
+//! command-line tools.
+
+pub mod size;
+#[cfg(feature = "test-support")]
+pub mod test_support;
+
+use std::{
+    fmt,
+    fs::File,
+    io::{self, BufRead, BufReader, Read, Write},
+    process,
+};
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug)]
+pub enum Error {
+    /// An I/O error while opening or reading the named file
+    File { path: String, source: io::Error },
+    /// A directory was given where a file was expected
+    IsDir(String),
+    /// An I/O error not tied to a particular file, e.g. writing stdout
+    Io(io::Error),
+    /// Any other fatal error, such as a bad argument value
+    Msg(String),
+    /// The problems were already reported; just exit with this status
+    Exit(i32),
+    /// Bad arguments, or a request for help or the version
+    Clap(clap::Error),
+}
+
+/// An opened input: stdin for "-", otherwise a regular file
+pub enum Input<R = io::Stdin> {
+    Stdin(R),
+    File(File),
+}
+
+/// A writer that notes whether writing to it ever failed
+pub struct Output<W> {
+    inner: W,
+    failed: bool,
+}
+
+// --------------------------------------------------
+impl Error {
+    pub fn file(path: &str, source: io::Error) -> Self {
+        Error::File {
+            path: path.to_string(),
+            source,
+        }
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::Exit(code) => *code,
+            Error::Clap(e) if e.use_stderr() => 2,
+            Error::Clap(_) => 0,
+            _ => 1,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::File { path, source } => write!(f, "{}: {}", path, source),
+            Error::IsDir(path) => write!(f, "{}: Is a directory", path),
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Msg(msg) => write!(f, "{}", msg),
+            Error::Exit(_) => Ok(()),
+            Error::Clap(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::File { source, .. } => Some(source),
+            Error::Io(e) => Some(e),
+            Error::Clap(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<clap::Error> for Error {
+    fn from(e: clap::Error) -> Self {
+        Error::Clap(e)
+    }
+}
+
+impl From<String> for Error {
+    fn from(msg: String) -> Self {
+        Error::Msg(msg)
+    }
+}
+
+impl From<&str> for Error {
+    fn from(msg: &str) -> Self {
+        Error::Msg(msg.to_string())
+    }
+}
+
+// --------------------------------------------------
+impl<R: Read> Read for Input<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Input::Stdin(stdin) => stdin.read(buf),
+            Input::File(file) => file.read(buf),
+        }
+    }
+}
+
+// --------------------------------------------------
+impl<W: Write> Write for Output<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let res = self.inner.write(buf);
+        // write_all makes its own error of a write that takes nothing, and
+        // retries an interrupted one
+        self.failed |= match &res {
+            Ok(0) => !buf.is_empty(),
+            Ok(_) => false,
+            Err(e) => e.kind() != io::ErrorKind::Interrupted,
+        };
+        res
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let res = self.inner.flush();
+        self.failed |= res.is_err();
+        res
+    }
+}
+
+impl<W: Write> Output<W> {
+    /// Copy all of `input` with `io::copy`, which on Linux leaves the work
+    /// to copy_file_range/sendfile/splice when both ends allow it. The
+    /// kernel doesn't say which end of such a copy failed, so only the
+    /// errors that can't come from reading are counted against the output.
+    pub fn copy_from(&mut self, input: &mut impl Read) -> io::Result<u64> {
+        let res = io::copy(input, &mut self.inner);
+        if let Err(e) = &res {
+            self.failed |= matches!(
+                e.kind(),
+                io::ErrorKind::BrokenPipe
+                    | io::ErrorKind::WriteZero
+                    | io::ErrorKind::StorageFull
+                    | io::ErrorKind::QuotaExceeded
+                    | io::ErrorKind::FileTooLarge
+            );
+        }
+        res
+    }
+}
+
+// --------------------------------------------------
+/// Call `f` to copy from the input `path` to `out`, putting down any I/O
+/// error that didn't come from writing to reading the input.
+pub fn reading<W: Write, T>(
+    path: &str,
+    out: W,
+    f: impl FnOnce(&mut Output<W>) -> Result<T>,
+) -> Result<T> {
+    let mut out = Output {
+        inner: out,
+        failed: false,
+    };
+    f(&mut out).map_err(|e| match e {
+        Error::Io(e) if !out.failed => Error::file(path, e),
+        e => e,
+    })
+}
+
+// --------------------------------------------------
+/// Open "-" as stdin or else the named file, rejecting directories.
+pub fn open_input(filename: &str) -> Result<Input> {
+    open_input_with(filename, io::stdin())
+}
+
+// --------------------------------------------------
+/// Like `open_input` but reading "-" from the given handle.
+pub fn open_input_with<R>(filename: &str, stdin: R) -> Result<Input<R>> {
+    match filename {
+        "-" => Ok(Input::Stdin(stdin)),
+        _ => {
+            let file =
+                File::open(filename).map_err(|e| Error::file(filename, e))?;
+            match file.metadata() {
+                Ok(meta) if meta.is_dir() => {
+                    Err(Error::IsDir(filename.to_string()))
+                }
+                Ok(_) => Ok(Input::File(file)),
+                Err(e) => Err(Error::file(filename, e)),
+            }
+        }
+    }
+}
+
+// --------------------------------------------------
+/// Like `open_input` but wrapped in a `BufReader`.
+pub fn open(filename: &str) -> Result<Box<dyn BufRead>> {
+    Ok(Box::new(BufReader::new(open_input(filename)?)))
+}
+
+// --------------------------------------------------
+/// Like `open` but reading "-" from the given handle.
+pub fn open_with<'a, R: BufRead + 'a>(
+    filename: &str,
+    stdin: R,
+) -> Result<Box<dyn BufRead + 'a>> {
+    match open_input_with(filename, stdin)? {
+        Input::Stdin(stdin) => Ok(Box::new(stdin)),
+        Input::File(file) => Ok(Box::new(BufReader::new(file))),
+    }
+}
+
+// --------------------------------------------------
+/// Print an error as "prog: message" on stderr.
+pub fn report(prog: &str, err: &Error) {
+    report_to(&mut io::stderr(), prog, err);
+}
+
+// --------------------------------------------------
+/// Like `report` but writing to the given handle.
+pub fn report_to(stderr: &mut impl Write, prog: &str, err: &Error) {
+    // There is nowhere left to report a failure to write the report
+    let _ = match err {
+        Error::Exit(_) => Ok(()),
+        Error::Clap(e) => write!(stderr, "{}", e),
+        _ => writeln!(stderr, "{}: {}", prog, err),
+    };
+}
+
+// --------------------------------------------------
+/// Report a fatal error and exit with its status.
+pub fn exit(prog: &str, err: Error) -> ! {
+    match err {
+        // Help and version go to stdout with a zero status
+        Error::Clap(e) => e.exit(),
+        _ => {
+            report(prog, &err);
+            process::exit(err.exit_code())
+        }
+    }
+}
+
+// --------------------------------------------------
+/// Exit for the tools that print their boxed errors unprefixed.
+pub fn exit_boxed(err: Box<dyn std::error::Error>) -> ! {
+    match err.downcast::<clap::Error>() {
+        Ok(e) => e.exit(),
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1)
+        }
+    }
+}
+
+// --------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::{
+        open, open_input, open_with, reading, report_to, Error, Input,
+    };
+    use std::io::{self, Cursor, Write};
+
+    #[test]
+    fn test_open_stdin() {
+        assert!(matches!(open_input("-"), Ok(Input::Stdin(_))));
+    }
+
+    #[test]
+    fn test_open_with() {
+        let mut stdin = Cursor::new("from stdin\n");
+        let mut file = open_with("-", &mut stdin).unwrap();
+        let mut line = String::new();
+        file.read_line(&mut line).unwrap();
+        assert_eq!(line, "from stdin\n");
+    }
+
+    #[test]
+    fn test_open_file() {
+        let mut file = open("Cargo.toml").unwrap();
+        let mut line = String::new();
+        file.read_line(&mut line).unwrap();
+        assert_eq!(line, "[package]\n");
+    }
+
+    #[test]
+    fn test_open_missing() {
+        let err = open_input("no-such-file").err().unwrap();
+        assert!(matches!(err, Error::File { .. }));
+        assert_eq!(
+            err.to_string(),
+            "no-such-file: No such file or directory (os error 2)"
+        );
+        assert_eq!(err.exit_code(), 1);
+    }
+
+    #[test]
+    fn test_open_dir() {
+        let err = open("src").err().unwrap();
+        assert!(matches!(err, Error::IsDir(_)));
+        assert_eq!(err.to_string(), "src: Is a directory");
+    }
+
+    #[test]
+    fn test_display() {
+        let err = Error::from(io::Error::other("boom"));
+        assert_eq!(err.to_string(), "boom");
+        assert_eq!(Error::from("bad value").to_string(), "bad value");
+        assert_eq!(Error::Exit(2).to_string(), "");
+        assert_eq!(Error::Exit(2).exit_code(), 2);
+    }
+
+    #[test]
+    fn test_reading() {
+        let err = reading("in.txt", vec![], |_| -> Result<(), Error> {
+            Err(io::Error::other("bad read").into())
+        })
+        .unwrap_err();
+        assert_eq!(err.to_string(), "in.txt: bad read");
+
+        // A write error is not the input's fault
+        let err = reading("in.txt", &mut [0u8; 2][..], |out| {
+            Ok(out.write_all(b"abc")?)
+        })
+        .unwrap_err();
+        assert!(matches!(err, Error::Io(_)));
+
+        let err = reading("in.txt", &mut [0u8; 2][..], |out| {
+            Ok(out.copy_from(&mut Cursor::new("abc"))?)
+        })
+        .unwrap_err();
+        assert!(matches!(err, Error::Io(_)));
+
+        let mut out = vec![];
+        let copied = reading("in.txt", &mut out, |out| {
+            Ok(out.copy_from(&mut Cursor::new("abc"))?)
+        });
+        assert_eq!(copied.unwrap(), 3);
+        assert_eq!(out, b"abc");
+    }
+
+    #[test]
+    fn test_report_to() {
+        let mut stderr = vec![];
+        report_to(&mut stderr, "prg", &Error::from("bad value"));
+        report_to(&mut stderr, "prg", &Error::Exit(1));
+        assert_eq!(stderr, b"prg: bad value\n");
+    }
+}
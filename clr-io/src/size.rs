@@ -0,0 +1,100 @@
+//! Counts with the multiplier suffixes GNU coreutils accept.
+
+use std::fmt;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum SizeError {
+    /// Not a number, or an unknown suffix
+    Invalid,
+    /// The value does not fit in a `u64`
+    TooLarge,
+}
+
+const POWERS: &str = "KMGTPEZYRQ";
+
+impl fmt::Display for SizeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SizeError::Invalid => write!(f, "invalid number"),
+            SizeError::TooLarge => write!(f, "value too large"),
+        }
+    }
+}
+
+impl std::error::Error for SizeError {}
+
+// --------------------------------------------------
+/// Parse a decimal count with an optional multiplier suffix: "b" is 512,
+/// "K", "M", "G", "T", "P", "E", "Z", "Y", "R" and "Q" are powers of 1024
+/// (as are "KiB", "MiB", ...), and "KB", "MB", ... are powers of 1000.
+/// "k" and "m" may stand in for "K" and "M".
+pub fn parse_size(val: &str) -> Result<u64, SizeError> {
+    let digits = val.find(|c: char| !c.is_ascii_digit()).unwrap_or(val.len());
+    if digits == 0 {
+        return Err(SizeError::Invalid);
+    }
+    let (num, suffix) = val.split_at(digits);
+    let num: u64 = num.parse().map_err(|_| SizeError::TooLarge)?;
+    num.checked_mul(multiplier(suffix)?)
+        .ok_or(SizeError::TooLarge)
+}
+
+// --------------------------------------------------
+fn multiplier(suffix: &str) -> Result<u64, SizeError> {
+    let mut chars = suffix.chars();
+    let power = match chars.next() {
+        None => return Ok(1),
+        Some('b') if chars.as_str().is_empty() => return Ok(512),
+        Some('k') => 1,
+        Some('m') => 2,
+        Some(c) => POWERS.find(c).ok_or(SizeError::Invalid)? + 1,
+    };
+    let base: u64 = match chars.as_str() {
+        "" | "iB" => 1024,
+        "B" => 1000,
+        _ => return Err(SizeError::Invalid),
+    };
+    base.checked_pow(power as u32).ok_or(SizeError::TooLarge)
+}
+
+// --------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::{parse_size, SizeError};
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("0"), Ok(0));
+        assert_eq!(parse_size("42"), Ok(42));
+        assert_eq!(parse_size("2b"), Ok(1024));
+        assert_eq!(parse_size("64K"), Ok(64 * 1024));
+        assert_eq!(parse_size("64k"), Ok(64 * 1024));
+        assert_eq!(parse_size("64KiB"), Ok(64 * 1024));
+        assert_eq!(parse_size("64KB"), Ok(64_000));
+        assert_eq!(parse_size("1MiB"), Ok(1 << 20));
+        assert_eq!(parse_size("1m"), Ok(1 << 20));
+        assert_eq!(parse_size("3MB"), Ok(3_000_000));
+        assert_eq!(parse_size("1G"), Ok(1 << 30));
+        assert_eq!(parse_size("1T"), Ok(1 << 40));
+        assert_eq!(parse_size("1E"), Ok(1 << 60));
+        assert_eq!(parse_size("18446744073709551615"), Ok(u64::MAX));
+    }
+
+    #[test]
+    fn test_parse_size_invalid() {
+        for val in ["", "K", "foo", "3.14", "-1", "+1", "1x", "1bB", "1Ki"] {
+            assert_eq!(parse_size(val), Err(SizeError::Invalid), "{}", val);
+        }
+        for val in ["1g", "1KiBs"] {
+            assert_eq!(parse_size(val), Err(SizeError::Invalid), "{}", val);
+        }
+    }
+
+    #[test]
+    fn test_parse_size_too_large() {
+        for val in ["18446744073709551616", "16E", "1Z", "1Y", "1Q", "1RB"] {
+            assert_eq!(parse_size(val), Err(SizeError::TooLarge), "{}", val);
+        }
+        assert_eq!(SizeError::TooLarge.to_string(), "value too large");
+    }
+}
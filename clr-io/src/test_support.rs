@@ -0,0 +1,14 @@
+//! Helpers shared by the tools' integration tests.
+
+/// A file that opens but can't be read: on Linux, reading /proc/self/mem
+/// from the start fails, as that page is never mapped.
+#[cfg(target_os = "linux")]
+pub const UNREADABLE: &str = "/proc/self/mem";
+
+// --------------------------------------------------
+/// The start of the message `prg` gives for failing to read
+/// [`UNREADABLE`], leaving out the kernel's wording of the error.
+#[cfg(target_os = "linux")]
+pub fn unreadable_error(prg: &str) -> String {
+    format!("{}: {}: ", prg, UNREADABLE)
+}
@@ -0,0 +1,170 @@
+use clr_io::Error;
+use std::{
+    env,
+    ffi::{OsStr, OsString},
+    path::Path,
+    process,
+};
+
+const PRG: &str = "clr";
+
+type Applet = fn(Vec<OsString>);
+
+// Command name, crate name and entry point
+type Entry = (&'static str, &'static str, Applet);
+
+// Run a tool whose errors are reported through clr-io
+macro_rules! applet {
+    ($tool:ident) => {
+        |args| {
+            if let Err(e) = $tool::get_args_from(args).and_then($tool::run) {
+                clr_io::exit(stringify!($tool), e);
+            }
+        }
+    };
+}
+
+// Run a tool that still returns a boxed error
+macro_rules! boxed_applet {
+    ($tool:ident) => {
+        |args| {
+            if let Err(e) = $tool::get_args_from(args).and_then($tool::run) {
+                clr_io::exit_boxed(e);
+            }
+        }
+    };
+}
+
+// Each applet answers to the name of the command it replaces and to
+// the name of the crate that implements it
+const APPLETS: &[Entry] = &[
+    ("cal", "calr", boxed_applet!(calr)),
+    ("cat", "catr", applet!(catr)),
+    ("comm", "commr", applet!(commr)),
+    ("cut", "cutr", applet!(cutr)),
+    ("echo", "echor", applet!(echor)),
+    ("find", "findr", boxed_applet!(findr)),
+    ("fortune", "fortuner", boxed_applet!(fortuner)),
+    ("grep", "grepr", applet!(grepr)),
+    ("head", "headr", applet!(headr)),
+    ("tail", "tailr", applet!(tailr)),
+    ("uniq", "uniqr", applet!(uniqr)),
+    ("wc", "wcr", applet!(wcr)),
+];
+
+// lsr reads Unix file modes and owners, so ls is only built there
+#[cfg(unix)]
+const UNIX_APPLETS: &[Entry] = &[("ls", "lsr", boxed_applet!(lsr))];
+
+#[cfg(not(unix))]
+const UNIX_APPLETS: &[Entry] = &[];
+
+// --------------------------------------------------
+fn main() {
+    let mut args = env::args_os();
+    let argv0 = args.next().unwrap_or_default();
+
+    // Invoked through a link named after one of the applets
+    let name = Path::new(&argv0).file_name();
+    if let Some(applet) = name.and_then(find_applet) {
+        return applet(env::args_os().collect());
+    }
+
+    let cmd = match args.next() {
+        Some(cmd) => cmd,
+        None => {
+            eprint!("{}", usage());
+            process::exit(1);
+        }
+    };
+
+    match cmd.to_str() {
+        Some("-h" | "--help") => print!("{}", usage()),
+        Some("-l" | "--list") => {
+            applets().for_each(|(name, _, _)| println!("{}", name))
+        }
+        Some("--install-links") => match (args.next(), args.next()) {
+            (Some(dir), None) => {
+                if let Err(e) = install_links(Path::new(&dir)) {
+                    clr_io::exit(PRG, e);
+                }
+            }
+            _ => {
+                eprint!("{}", usage());
+                process::exit(1);
+            }
+        },
+        _ => match find_applet(&cmd) {
+            // The command name stands in for argv[0]
+            Some(applet) => applet([cmd].into_iter().chain(args).collect()),
+            None => clr_io::exit(
+                PRG,
+                Error::Msg(format!(
+                    "unknown command \"{}\"; try '{} --list'",
+                    cmd.to_string_lossy(),
+                    PRG
+                )),
+            ),
+        },
+    }
+}
+
+// --------------------------------------------------
+fn usage() -> String {
+    let names: Vec<_> = applets().map(|(name, _, _)| *name).collect();
+    format!(
+        "Usage: {prg} COMMAND [ARG]...\n       \
+         {prg} --install-links DIR\n       \
+         {prg} --list\n\n\
+         Run COMMAND, or the command named by the link {prg} was \
+         invoked through.\n\n\
+         Commands: {}\n",
+        names.join(" "),
+        prg = PRG
+    )
+}
+
+// --------------------------------------------------
+fn applets() -> impl Iterator<Item = &'static Entry> {
+    APPLETS.iter().chain(UNIX_APPLETS)
+}
+
+// --------------------------------------------------
+fn find_applet(name: &OsStr) -> Option<Applet> {
+    let name = name.to_str()?;
+    applets()
+        .find(|(cmd, tool, _)| *cmd == name || *tool == name)
+        .map(|(_, _, applet)| *applet)
+}
+
+// --------------------------------------------------
+#[cfg(unix)]
+fn install_links(dir: &Path) -> clr_io::Result<()> {
+    use std::os::unix::fs::symlink;
+
+    let exe = env::current_exe()?;
+    let mut failed = false;
+    for (name, _, _) in applets() {
+        let link = dir.join(name);
+        if let Err(e) = symlink(&exe, &link) {
+            clr_io::report(PRG, &Error::file(&link.to_string_lossy(), e));
+            failed = true;
+        }
+    }
+
+    if failed {
+        Err(Error::Exit(1))
+    } else {
+        Ok(())
+    }
+}
+
+// --------------------------------------------------
+#[cfg(not(unix))]
+fn install_links(_dir: &Path) -> clr_io::Result<()> {
+    Err(Error::Msg(
+        "--install-links needs symbolic links, which are only supported \
+         on Unix"
+            .to_string(),
+    ))
+}
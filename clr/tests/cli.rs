@@ -0,0 +1,137 @@
+use assert_cmd::{cargo::cargo_bin, Command};
+use predicates::prelude::*;
+use std::error::Error;
+use std::fs;
+use tempfile::tempdir;
+
+type TestResult = Result<(), Box<dyn Error>>;
+
+const PRG: &str = "clr";
+const FOX: &str = "tests/inputs/fox.txt";
+
+// --------------------------------------------------
+#[test]
+fn usage() -> TestResult {
+    for flag in &["-h", "--help"] {
+        Command::cargo_bin(PRG)?
+            .arg(flag)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Usage: clr COMMAND"));
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_no_args() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Usage: clr COMMAND"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_unknown_command() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["blargh", FOX])
+        .assert()
+        .failure()
+        .stderr("clr: unknown command \"blargh\"; try 'clr --list'\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn list() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .arg("--list")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("cat\n"))
+        .stdout(predicate::str::contains("wc\n"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn subcommand() -> TestResult {
+    for cmd in &["cat", "catr"] {
+        Command::cargo_bin(PRG)?
+            .args([cmd, "-n", FOX])
+            .assert()
+            .success()
+            .stdout("     1\tThe quick brown fox jumps over the lazy dog.\n");
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn subcommand_help() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["echo", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Rust echo"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn subcommand_stdin() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["wc", "-l"])
+        .write_stdin("a\nb\n")
+        .assert()
+        .success()
+        .stdout("2\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn subcommand_error() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["head", "-n", "foo", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("headr: illegal line count -- foo"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(unix)]
+fn install_links() -> TestResult {
+    let dir = tempdir()?;
+    Command::cargo_bin(PRG)?
+        .arg("--install-links")
+        .arg(dir.path())
+        .assert()
+        .success();
+
+    let exe = fs::canonicalize(cargo_bin(PRG))?;
+    for name in &["cat", "head", "wc", "uniq", "find", "grep"] {
+        let link = dir.path().join(name);
+        assert_eq!(fs::canonicalize(&link)?, exe);
+    }
+
+    // The links dispatch on the name they were invoked through
+    Command::new(dir.path().join("head"))
+        .args(["-c", "3", FOX])
+        .assert()
+        .success()
+        .stdout("The");
+
+    // Existing files are not overwritten
+    Command::cargo_bin(PRG)?
+        .arg("--install-links")
+        .arg(dir.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cat: File exists"));
+    Ok(())
+}
@@ -2,7 +2,7 @@ use clap::{App, Arg};
 use rand::{distributions::Alphanumeric, Rng};
 use std::error::Error;
 use std::fs::File;
-use std::io::Write;
+use std::io::{self, BufWriter, Write};
 use thousands::Separable;
 
 type MyResult<T> = Result<T, Box<dyn Error>>;
@@ -59,15 +59,9 @@ fn parse_positive_int(val: &str) -> MyResult<usize> {
 
 // --------------------------------------------------
 pub fn run(config: Config) -> MyResult<()> {
-    let mut file = File::create(&config.outfile)?;
-    for _ in 0..config.lines {
-        let num_words = rand::thread_rng().gen_range(7..15);
-        let mut words = vec![];
-        for _ in 0..num_words {
-            words.push(random_string());
-        }
-        writeln!(file, "{}", words.join(" "))?;
-    }
+    let mut file = BufWriter::new(File::create(&config.outfile)?);
+    write_lines(&mut file, config.lines)?;
+    file.flush()?;
 
     println!(
         "Done, wrote {} line{} to \"{}\".",
@@ -79,6 +73,19 @@ pub fn run(config: Config) -> MyResult<()> {
     Ok(())
 }
 
+// --------------------------------------------------
+pub fn write_lines(out: &mut impl Write, lines: usize) -> io::Result<()> {
+    for _ in 0..lines {
+        let num_words = rand::thread_rng().gen_range(7..15);
+        let mut words = vec![];
+        for _ in 0..num_words {
+            words.push(random_string());
+        }
+        writeln!(out, "{}", words.join(" "))?;
+    }
+    Ok(())
+}
+
 // --------------------------------------------------
 fn random_string() -> String {
     let length = rand::thread_rng().gen_range(2..8);